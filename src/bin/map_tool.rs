@@ -0,0 +1,149 @@
+//! 地图格式转换和检查工具,格式按扩展名区分:.ron游戏地图、.txt文本地图、.json Tiled地图
+//!
+//! map_tool convert <输入> <输出> [--map <序号>]
+//! map_tool validate <文件>
+//! map_tool generate <输出> [选项]
+
+use std::process::ExitCode;
+
+use bevy_tank::{
+    res::EditorMirror,
+    utils::{
+        generator::{HomeFortress, MapGenerator},
+        map_io::{export_maps, load_maps, validate_maps, MapFormatError},
+    },
+};
+
+const USAGE: &str = "usage:
+  map_tool convert <input> <output> [--map <index>]
+  map_tool validate <file>
+  map_tool generate <output> [--seed <n>] [--count <n>] [--density <0-1>] [--iron <0-1>]
+                    [--water <n>] [--trees <n>] [--symmetry none|lr|tb|quad]
+                    [--fortress none|brick|iron]
+formats: .ron (game maps), .txt (text grid), .json (Tiled map)";
+
+fn convert(input: &str, output: &str, index: Option<usize>) -> Result<(), MapFormatError> {
+    let mut maps = load_maps(input)?;
+    //Tiled地图一个文件一张,用--map选择其中一张
+    if let Some(index) = index {
+        if index >= maps.len() {
+            return Err(MapFormatError::MapIndex {
+                index,
+                count: maps.len(),
+            });
+        }
+        maps = vec![maps.swap_remove(index)];
+    }
+    validate_maps(&maps)?;
+    export_maps(output, &maps)?;
+    println!("converted {} map(s) to {}", maps.len(), output);
+    Ok(())
+}
+
+fn validate(path: &str) -> Result<bool, MapFormatError> {
+    let maps = load_maps(path)?;
+    let mut valid = true;
+    for (index, game_map) in maps.iter().enumerate() {
+        match game_map.validate() {
+            Ok(()) => println!("{} {}: ok", index, game_map.name),
+            Err(error) => {
+                println!("{} {}: {}", index, game_map.name, error);
+                valid = false;
+            }
+        }
+    }
+    Ok(valid)
+}
+
+///按选项生成count张地图,种子依次加一
+fn generate(output: &str, options: &[&str]) -> Result<bool, MapFormatError> {
+    let mut generator = MapGenerator::default();
+    let mut count = 1;
+    for option in options.chunks(2) {
+        let [name, value] = option else {
+            return Ok(false);
+        };
+        let parsed = match *name {
+            "--seed" => value.parse().map(|seed| generator.seed = seed).is_ok(),
+            "--count" => value.parse().map(|n| count = n).is_ok(),
+            "--density" => value.parse().map(|n| generator.wall_density = n).is_ok(),
+            "--iron" => value.parse().map(|n| generator.iron_ratio = n).is_ok(),
+            "--water" => value.parse().map(|n| generator.water_clusters = n).is_ok(),
+            "--trees" => value.parse().map(|n| generator.tree_clusters = n).is_ok(),
+            "--symmetry" => {
+                let symmetry = match *value {
+                    "none" => Some(EditorMirror::None),
+                    "lr" => Some(EditorMirror::Horizontal),
+                    "tb" => Some(EditorMirror::Vertical),
+                    "quad" => Some(EditorMirror::Quad),
+                    _ => None,
+                };
+                symmetry
+                    .map(|symmetry| generator.symmetry = symmetry)
+                    .is_some()
+            }
+            "--fortress" => {
+                let fortress = match *value {
+                    "none" => Some(HomeFortress::None),
+                    "brick" => Some(HomeFortress::Brick),
+                    "iron" => Some(HomeFortress::Iron),
+                    _ => None,
+                };
+                fortress
+                    .map(|fortress| generator.fortress = fortress)
+                    .is_some()
+            }
+            _ => false,
+        };
+        if !parsed {
+            return Ok(false);
+        }
+    }
+    let maps: Vec<_> = (0..count)
+        .map(|i| {
+            let generator = MapGenerator {
+                seed: generator.seed.wrapping_add(i),
+                ..generator.clone()
+            };
+            generator.generate(format!("gen{}", generator.seed))
+        })
+        .collect();
+    validate_maps(&maps)?;
+    export_maps(output, &maps)?;
+    println!("generated {} map(s) to {}", maps.len(), output);
+    Ok(true)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["convert", input, output] => convert(input, output, None).map(|_| true),
+        ["convert", input, output, "--map", index] => match index.parse() {
+            Ok(index) => convert(input, output, Some(index)).map(|_| true),
+            Err(_) => {
+                eprintln!("{}", USAGE);
+                return ExitCode::FAILURE;
+            }
+        },
+        ["validate", path] => validate(path),
+        ["generate", output, ref options @ ..] => match generate(output, options) {
+            Ok(false) => {
+                eprintln!("{}", USAGE);
+                return ExitCode::FAILURE;
+            }
+            result => result,
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -1,5 +1,23 @@
 use bevy::prelude::*;
-use crate::res::{GameMapCollection, GameState, HandleLoadMap};
+use std::path::PathBuf;
+
+#[cfg(feature = "hot_reload")]
+use crate::{
+    res::{
+        EditorHistory, EditorTestPlay, GameSettings, MapReloaded, UISelectInfo, GAME_SETTINGS_PATH,
+    },
+    utils::{
+        map_io::{load_map_pack, validate_maps},
+        util::{load_settings, user_map_pack_path},
+    },
+};
+use crate::{
+    res::{
+        GameMapCollection, GameState, HandleLoadMap, MapPack, MapPackSource, MapPacks,
+        GAME_MAP_PATH,
+    },
+    utils::util::load_map_packs,
+};
 
 pub fn setup_load_game_map_resource(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.insert_resource(HandleLoadMap(asset_server.load("map.ron")));
@@ -8,14 +26,167 @@ pub fn setup_load_game_map_resource(mut commands: Commands, asset_server: Res<As
 pub fn update_load_to_ui_menu(
     mut commands: Commands,
     map_handle: Res<HandleLoadMap>,
-    mut maps: ResMut<Assets<GameMapCollection>>,
+    maps: Res<Assets<GameMapCollection>>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
-    if let Some(mut map) = maps.remove(map_handle.0.id()) {
+    //不移除资源,文件变化时资源才会重新加载
+    if let Some(map) = maps.get(map_handle.0.id()) {
+        let mut map = map.clone();
         for m in map.maps.iter_mut() {
             m.init_fixed();
         }
+        //map.ron是第一个地图包,之后是地图包目录和用户目录中的地图包
+        let mut packs = vec![MapPack {
+            id: String::new(),
+            path: PathBuf::from(GAME_MAP_PATH),
+            source: MapPackSource::BuiltIn,
+            maps: map.clone(),
+        }];
+        packs.extend(load_map_packs());
+        commands.insert_resource(MapPacks { packs, current: 0 });
         commands.insert_resource(map);
         next_state.set(GameState::UIMenu);
     }
-}
\ No newline at end of file
+}
+
+///map.ron重新加载后检查并更新内置地图包,正在使用内置地图包时替换地图;内容没有变化时忽略
+#[cfg(feature = "hot_reload")]
+#[allow(clippy::too_many_arguments)]
+pub fn update_hot_reload_map(
+    mut events: EventReader<AssetEvent<GameMapCollection>>,
+    map_handle: Res<HandleLoadMap>,
+    maps: Res<Assets<GameMapCollection>>,
+    test_play: Res<EditorTestPlay>,
+    mut packs: ResMut<MapPacks>,
+    mut gm_map: ResMut<GameMapCollection>,
+    mut ui_selector: ResMut<UISelectInfo>,
+    mut history: ResMut<EditorHistory>,
+    mut reloaded: EventWriter<MapReloaded>,
+) {
+    let modified = events
+        .read()
+        .any(|event| event.is_modified(map_handle.0.id()));
+    let Some(map) = maps.get(map_handle.0.id()).filter(|_| modified) else {
+        return;
+    };
+    let mut map = map.clone();
+    for m in map.maps.iter_mut() {
+        m.init_fixed();
+    }
+    if map.maps.is_empty() {
+        println!("hot reload map.ron error: no map");
+        return;
+    }
+    if let Err(err) = validate_maps(&map.maps) {
+        println!("hot reload map.ron error: {}", err);
+        return;
+    }
+    let built_in = &mut packs.packs[0].maps;
+    if serde_ron::to_string(&map).ok() == serde_ron::to_string(built_in).ok() {
+        return;
+    }
+    println!("hot reload map.ron: {} maps", map.maps.len());
+    built_in.clone_from(&map);
+    //试玩时不替换,回到编辑器后仍是试玩前编辑的地图
+    if packs.current != 0 || test_play.active() {
+        return;
+    }
+    replace_reloaded_map(map, &mut gm_map, &mut ui_selector, &mut history);
+    reloaded.send(MapReloaded);
+}
+
+///用户地图包在assets之外,和设置文件一样每秒检查一次修改时间;编辑器保存时内容和地图包相同,不会重复读取
+#[cfg(feature = "hot_reload")]
+#[allow(clippy::too_many_arguments)]
+pub fn update_hot_reload_user_pack(
+    time: Res<Time>,
+    test_play: Res<EditorTestPlay>,
+    mut packs: ResMut<MapPacks>,
+    mut gm_map: ResMut<GameMapCollection>,
+    mut ui_selector: ResMut<UISelectInfo>,
+    mut history: ResMut<EditorHistory>,
+    mut reloaded: EventWriter<MapReloaded>,
+    mut timer: Local<Option<Timer>>,
+    mut last_modified: Local<Option<std::time::SystemTime>>,
+) {
+    let timer = timer.get_or_insert_with(|| Timer::from_seconds(1., TimerMode::Repeating));
+    if !timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let path = user_map_pack_path();
+    let Ok(modified) = std::fs::metadata(&path).and_then(|meta| meta.modified()) else {
+        return;
+    };
+    if last_modified
+        .replace(modified)
+        .is_none_or(|last| last == modified)
+    {
+        return;
+    }
+    let Some(index) = packs.user_index() else {
+        return;
+    };
+    let map = match load_map_pack(&path) {
+        Ok(map) => map,
+        Err(err) => {
+            println!("hot reload {} error: {}", path.display(), err);
+            return;
+        }
+    };
+    let user_pack = &mut packs.packs[index].maps;
+    if serde_ron::to_string(&map).ok() == serde_ron::to_string(user_pack).ok() {
+        return;
+    }
+    println!("hot reload {}: {} maps", path.display(), map.maps.len());
+    user_pack.clone_from(&map);
+    if packs.current != index || test_play.active() {
+        return;
+    }
+    replace_reloaded_map(map, &mut gm_map, &mut ui_selector, &mut history);
+    reloaded.send(MapReloaded);
+}
+
+///替换正在使用的地图,选择的地图超出范围时移到最后一张
+#[cfg(feature = "hot_reload")]
+fn replace_reloaded_map(
+    map: GameMapCollection,
+    gm_map: &mut GameMapCollection,
+    ui_selector: &mut UISelectInfo,
+    history: &mut EditorHistory,
+) {
+    let last = map.maps.len() - 1;
+    *gm_map = map;
+    ui_selector.map_index = ui_selector.map_index.min(last);
+    ui_selector.map_editor_level_index = ui_selector.map_editor_level_index.min(last);
+    //地图被整体替换,旧的撤销记录不再对应
+    *history = EditorHistory::default();
+}
+
+///设置文件不通过资源加载,每秒检查一次修改时间,变化后重新读取;内容相同时忽略,游戏保存设置也会改变修改时间
+#[cfg(feature = "hot_reload")]
+pub fn update_hot_reload_settings(
+    time: Res<Time>,
+    mut settings: ResMut<GameSettings>,
+    mut timer: Local<Option<Timer>>,
+    mut last_modified: Local<Option<std::time::SystemTime>>,
+) {
+    let timer = timer.get_or_insert_with(|| Timer::from_seconds(1., TimerMode::Repeating));
+    if !timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let Ok(modified) = std::fs::metadata(GAME_SETTINGS_PATH).and_then(|meta| meta.modified())
+    else {
+        return;
+    };
+    if last_modified
+        .replace(modified)
+        .is_none_or(|last| last == modified)
+    {
+        return;
+    }
+    let new_settings = load_settings();
+    if serde_ron::to_string(&new_settings).ok() != serde_ron::to_string(settings.as_ref()).ok() {
+        println!("hot reload {}", GAME_SETTINGS_PATH);
+        *settings = new_settings;
+    }
+}
@@ -1,4 +1,4 @@
-use bevy::prelude::*;
+use bevy::{diagnostic::FrameTimeDiagnosticsPlugin, input::InputSystem, prelude::*};
 use bevy_asset_loader::{
     loading_state::{config::ConfigureLoadingState, LoadingState, LoadingStateAppExt},
     standard_dynamic_asset::StandardDynamicAssetCollection,
@@ -8,20 +8,57 @@ use idgenerator::{IdGeneratorOptions, IdInstance};
 use crate::{
     load::{setup_load_game_map_resource, update_load_to_ui_menu},
     res::{
-        Clear, GameMapCollection, GameSettings, GameSource, GameState, LastSelectInfo,
-        UISelectInfo, INITIAL_SETTINGS,
+        Clear, EditorClipboard, EditorHistory, EditorTestPlay, GameEvent, GameMapCollection,
+        GameRecord, GameSettings, GameSource, GameState, LastSelectInfo, Locale, MapReloaded,
+        MenuInput, PauseMenu, PauseState, UISelectInfo, INITIAL_SETTINGS,
     },
     ui::{
         class::despawn_screen,
-        editor::{setup_ui_editor, update_ui_editor, update_ui_editor_brush},
+        editor::{
+            exit_editor_test_play, setup_editor_user_pack, setup_ui_editor,
+            update_editor_test_play_end, update_ui_editor, update_ui_editor_analysis,
+            update_ui_editor_brush, update_ui_editor_clipboard, update_ui_editor_history,
+            update_ui_editor_map_reloaded, update_ui_editor_maps, update_ui_editor_meta,
+            update_ui_editor_pattern, update_ui_editor_rename, update_ui_editor_reorder,
+            update_ui_editor_save, update_ui_editor_share_code, update_ui_editor_test_play,
+            update_ui_editor_tool_button, update_ui_editor_tools,
+        },
         game::{
-            setup_ui_game, update_bullet_boom, update_check_collision, update_ui_enemy,
+            setup_ui_game, update_bullet_boom, update_check_collision, update_enemy_spawn,
+            update_game_over, update_player_record, update_player_respawn, update_ui_enemy,
             update_ui_game, update_ui_game_shoot,
         },
+        hud::{setup_game_record, setup_ui_hud, update_game_record, update_ui_hud},
+        keys::{exit_ui_keys, setup_ui_keys, update_ui_keys},
         menu::{setup_ui_menu, update_ui_menu},
+        pack::{setup_ui_map_pack, update_ui_map_pack},
+        pause::{
+            exit_ui_game_pause, exit_ui_pause, setup_ui_game_restart, setup_ui_pause,
+            update_ui_game_pause, update_ui_pause,
+        },
+        power_up::{
+            update_power_up_drop, update_power_up_effect, update_power_up_iron,
+            update_power_up_pick,
+        },
+        settings::{
+            exit_ui_settings, setup_fps, setup_ui_settings, update_fps, update_settings_apply,
+            update_ui_settings,
+        },
+        stage::{setup_new_game, setup_ui_stage, update_stage_clear, update_ui_stage},
         widget::{wd_update_collapse_grid, wd_update_node_block},
     },
-    utils::{animate::AnimatorPlugin, ron::RonAssetPlugin},
+    utils::{
+        animate::AnimatorPlugin,
+        i18n::{update_locale, update_ui_translate},
+        input::update_menu_input,
+        ron::RonAssetPlugin,
+        util::{load_progress, load_settings, load_stamps},
+    },
+};
+#[cfg(feature = "hot_reload")]
+use crate::{
+    load::{update_hot_reload_map, update_hot_reload_settings, update_hot_reload_user_pack},
+    res::MapPacks,
 };
 
 pub struct GamePlugin;
@@ -30,19 +67,35 @@ impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         let opt = IdGeneratorOptions::new().worker_id(1).worker_id_bit_len(6);
         let _ = IdInstance::set_options(opt);
+        //窗口创建前读取设置
+        let settings = load_settings();
+        let window = Window {
+            title: INITIAL_SETTINGS.win_title.into(),
+            resolution: settings.resolution.into(),
+            mode: settings.window_mode,
+            ..default()
+        };
         app.init_state::<GameState>()
-            .insert_resource(GameSettings::default())
+            .init_state::<PauseState>()
+            .insert_resource(settings)
             .insert_resource(UISelectInfo::default())
             .insert_resource(LastSelectInfo::default())
+            .insert_resource(GameRecord::default())
+            .insert_resource(MenuInput::default())
+            .insert_resource(Locale::default())
+            .insert_resource(EditorHistory::default())
+            .insert_resource(EditorClipboard::default())
+            .insert_resource(EditorTestPlay::default())
+            .insert_resource(load_stamps())
+            .insert_resource(load_progress())
+            .add_event::<GameEvent>()
+            .add_event::<MapReloaded>()
             .add_plugins((
                 DefaultPlugins.set(WindowPlugin {
-                    primary_window: Some(Window {
-                        title: INITIAL_SETTINGS.win_title.into(),
-                        resolution: INITIAL_SETTINGS.win_resolution.into(),
-                        ..default()
-                    }),
+                    primary_window: Some(window),
                     ..default()
                 }),
+                FrameTimeDiagnosticsPlugin,
                 RonAssetPlugin::<GameMapCollection>::default(),
                 AnimatorPlugin,
             ))
@@ -53,9 +106,25 @@ impl Plugin for GamePlugin {
                     .with_dynamic_assets_file::<StandardDynamicAssetCollection>("load.assets.ron")
                     .load_collection::<GameSource>(),
             )
-            .add_systems(Startup, setup_camera)
+            .add_systems(Startup, (setup_camera, setup_fps))
+            .add_systems(
+                Update,
+                (
+                    update_settings_apply.run_if(resource_changed::<GameSettings>),
+                    update_fps,
+                    update_locale,
+                    update_ui_translate
+                        .after(update_locale)
+                        .run_if(resource_exists::<GameSource>),
+                ),
+            )
+            .add_systems(PreUpdate, update_menu_input.after(InputSystem))
             .add_systems(OnExit(GameState::UIMenu), despawn_screen::<Clear>)
-            .add_systems(OnExit(GameState::UIGameEnter), despawn_screen::<Clear>)
+            .add_systems(OnExit(GameState::UIStage), despawn_screen::<Clear>)
+            .add_systems(
+                OnExit(GameState::UIGameEnter),
+                (despawn_screen::<Clear>, exit_ui_game_pause),
+            )
             .add_systems(OnExit(GameState::UIMapEditor), despawn_screen::<Clear>)
             .add_systems(Startup, setup_load_game_map_resource)
             .add_systems(
@@ -64,7 +133,25 @@ impl Plugin for GamePlugin {
             )
             .add_systems(OnEnter(GameState::UIMenu), setup_ui_menu)
             .add_systems(Update, update_ui_menu.run_if(in_state(GameState::UIMenu)))
-            .add_systems(OnEnter(GameState::UIGameEnter), setup_ui_game)
+            .add_systems(OnEnter(GameState::UIMapPack), setup_ui_map_pack)
+            .add_systems(OnExit(GameState::UIMapPack), despawn_screen::<Clear>)
+            .add_systems(
+                Update,
+                update_ui_map_pack.run_if(in_state(GameState::UIMapPack)),
+            )
+            .add_systems(
+                OnTransition {
+                    from: GameState::UIMapPack,
+                    to: GameState::UIStage,
+                },
+                setup_new_game,
+            )
+            .add_systems(OnEnter(GameState::UIStage), setup_ui_stage)
+            .add_systems(Update, update_ui_stage.run_if(in_state(GameState::UIStage)))
+            .add_systems(
+                OnEnter(GameState::UIGameEnter),
+                (setup_ui_game, (setup_game_record, setup_ui_hud).chain()),
+            )
             .add_systems(
                 Update,
                 (
@@ -73,12 +160,69 @@ impl Plugin for GamePlugin {
                     update_check_collision,
                     update_ui_game_shoot,
                     update_bullet_boom,
+                    update_enemy_spawn,
+                    update_player_respawn,
+                    update_player_record,
+                    update_power_up_drop.after(update_bullet_boom),
+                    update_power_up_pick,
+                    update_power_up_effect.after(update_power_up_pick),
+                    update_power_up_iron.after(update_game_record),
+                    update_game_over.after(update_bullet_boom),
+                    update_game_record.after(update_bullet_boom),
+                    update_stage_clear.after(update_game_record),
+                    update_ui_hud
+                        .after(update_game_record)
+                        .run_if(resource_changed::<GameRecord>),
+                )
+                    .run_if(in_state(GameState::UIGameEnter))
+                    .run_if(in_state(PauseState::Running)),
+            )
+            .add_systems(
+                Update,
+                (
+                    update_ui_game_pause,
+                    update_ui_pause
+                        .after(update_ui_game_pause)
+                        .run_if(in_state(PauseState::Paused)),
                 )
                     .run_if(in_state(GameState::UIGameEnter)),
             )
+            .add_systems(OnEnter(GameState::UIGameRestart), setup_ui_game_restart)
+            .add_systems(OnEnter(PauseState::Paused), setup_ui_pause)
+            .add_systems(
+                OnExit(PauseState::Paused),
+                (despawn_screen::<PauseMenu>, exit_ui_pause),
+            )
+            .add_systems(OnEnter(GameState::UIKeysSetting), setup_ui_keys)
+            .add_systems(
+                OnExit(GameState::UIKeysSetting),
+                (despawn_screen::<Clear>, exit_ui_keys),
+            )
+            .add_systems(
+                Update,
+                update_ui_keys.run_if(in_state(GameState::UIKeysSetting)),
+            )
+            .add_systems(OnEnter(GameState::UISettings), setup_ui_settings)
+            .add_systems(
+                OnExit(GameState::UISettings),
+                (despawn_screen::<Clear>, exit_ui_settings),
+            )
+            .add_systems(
+                Update,
+                update_ui_settings.run_if(in_state(GameState::UISettings)),
+            )
             .add_systems(
                 OnEnter(GameState::UIMapEditor),
-                (setup_ui_editor, setup_ui_game),
+                (
+                    setup_editor_user_pack,
+                    exit_editor_test_play,
+                    (setup_ui_editor, setup_ui_game),
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                update_editor_test_play_end.run_if(in_state(GameState::UIGameEnter)),
             )
             .add_systems(
                 Update,
@@ -86,10 +230,33 @@ impl Plugin for GamePlugin {
                     update_ui_editor,
                     wd_update_collapse_grid,
                     wd_update_node_block,
+                    update_ui_editor_history.before(update_ui_editor_brush),
                     update_ui_editor_brush,
+                    update_ui_editor_maps,
+                    update_ui_editor_rename,
+                    update_ui_editor_save,
+                    update_ui_editor_tool_button,
+                    update_ui_editor_pattern,
+                    update_ui_editor_clipboard.after(update_ui_editor_history),
+                    update_ui_editor_test_play,
+                    update_ui_editor_tools.after(update_ui_editor_history),
+                    update_ui_editor_meta,
+                    update_ui_editor_reorder.after(update_ui_editor),
+                    update_ui_editor_analysis,
+                    update_ui_editor_map_reloaded,
+                    update_ui_editor_share_code.after(update_ui_editor_meta),
                 )
                     .run_if(in_state(GameState::UIMapEditor)),
             );
+        #[cfg(feature = "hot_reload")]
+        app.add_systems(
+            Update,
+            (
+                update_hot_reload_map.run_if(resource_exists::<GameMapCollection>),
+                update_hot_reload_user_pack.run_if(resource_exists::<MapPacks>),
+                update_hot_reload_settings,
+            ),
+        );
     }
 }
 
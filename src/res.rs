@@ -1,10 +1,18 @@
-use std::time::Duration;
+use std::{
+    collections::{HashMap, VecDeque},
+    path::PathBuf,
+    time::Duration,
+};
 
-use bevy::prelude::*;
+use bevy::{prelude::*, window::WindowMode};
 use bevy_asset_loader::asset_collection::AssetCollection;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
-use crate::plugins::gen_id;
+use crate::{
+    plugins::gen_id,
+    utils::{migrate::deserialize_grid, util::moved_index},
+};
 
 ///全局游戏状态
 #[derive(Default, States, Debug, Hash, PartialEq, Eq, Clone)]
@@ -13,19 +21,228 @@ pub enum GameState {
     LoadTexture,
     LoadResource,
     UIMenu,
+    UIMapPack,
+    UIStage,
     UIGameEnter,
+    ///暂停菜单选择重新开始时经过的状态,进入后马上回到UIGameEnter
+    UIGameRestart,
     Gaming,
     UIMapEditor,
+    UIKeysSetting,
+    UISettings,
+}
+///游戏进行中的暂停状态
+#[derive(Default, States, Debug, Hash, PartialEq, Eq, Clone)]
+pub enum PauseState {
+    #[default]
+    Running,
+    Paused,
 }
 /* -----------Component--------------- */
 #[derive(Component, Clone, Copy, Eq, PartialEq)]
 pub enum RightPanelButton {
     SaveMap,
     NewMap,
+    GenerateMap,
     DeleteMap,
+    Mirror,
+    TestPlay,
+    MapInfo,
+    MoveUp,
+    MoveDown,
+    Analysis,
     Back,
 }
 
+///地图编辑器的绘制工具,同时作为右侧面板中工具按钮的组件
+#[derive(Component, Default, Clone, Copy, Eq, PartialEq, Debug)]
+pub enum EditorTool {
+    #[default]
+    Pencil,
+    Rect,
+    RectOutline,
+    Line,
+    Fill,
+    Eraser,
+    Eyedropper,
+    Select,
+}
+
+impl EditorTool {
+    pub const ALL: [EditorTool; 8] = [
+        EditorTool::Pencil,
+        EditorTool::Rect,
+        EditorTool::RectOutline,
+        EditorTool::Line,
+        EditorTool::Fill,
+        EditorTool::Eraser,
+        EditorTool::Eyedropper,
+        EditorTool::Select,
+    ];
+    ///工具名称的翻译key
+    pub fn key(&self) -> &'static str {
+        match self {
+            EditorTool::Pencil => "editor.tool.pencil",
+            EditorTool::Rect => "editor.tool.rect",
+            EditorTool::RectOutline => "editor.tool.rect_outline",
+            EditorTool::Line => "editor.tool.line",
+            EditorTool::Fill => "editor.tool.fill",
+            EditorTool::Eraser => "editor.tool.eraser",
+            EditorTool::Eyedropper => "editor.tool.eyedropper",
+            EditorTool::Select => "editor.tool.select",
+        }
+    }
+    ///拖动后松开鼠标才绘制的工具
+    pub fn is_drag(&self) -> bool {
+        matches!(
+            self,
+            EditorTool::Rect | EditorTool::RectOutline | EditorTool::Line
+        )
+    }
+}
+///编辑器图案库中的图案,值为GAME_EDITOR_PATTERNS的index
+#[derive(Component, Clone, Copy)]
+pub struct EditorPattern(pub usize);
+///编辑器画笔的镜像模式,同时绘制左右、上下或四个方向对称的位置
+#[derive(Default, Clone, Copy, Eq, PartialEq, Debug)]
+pub enum EditorMirror {
+    #[default]
+    None,
+    Horizontal,
+    Vertical,
+    Quad,
+}
+
+impl EditorMirror {
+    pub fn next(&self) -> Self {
+        match self {
+            EditorMirror::None => EditorMirror::Horizontal,
+            EditorMirror::Horizontal => EditorMirror::Vertical,
+            EditorMirror::Vertical => EditorMirror::Quad,
+            EditorMirror::Quad => EditorMirror::None,
+        }
+    }
+    ///是否(左右, 上下)镜像
+    pub fn flips(&self) -> (bool, bool) {
+        match self {
+            EditorMirror::None => (false, false),
+            EditorMirror::Horizontal => (true, false),
+            EditorMirror::Vertical => (false, true),
+            EditorMirror::Quad => (true, true),
+        }
+    }
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            EditorMirror::None => "editor.mirror.none",
+            EditorMirror::Horizontal => "editor.mirror.horizontal",
+            EditorMirror::Vertical => "editor.mirror.vertical",
+            EditorMirror::Quad => "editor.mirror.quad",
+        }
+    }
+}
+///编辑器镜像按钮的文字
+#[derive(Component)]
+pub struct EditorMirrorText;
+///编辑器中显示地图分析结果的文字
+#[derive(Component)]
+pub struct EditorAnalysisText;
+///地图信息面板中可以编辑的项
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum MapMetaField {
+    Name,
+    Author,
+    Enemy(usize),
+    TimeLimit,
+    DropRate,
+    Music,
+}
+
+impl MapMetaField {
+    pub const ALL: [MapMetaField; 8] = [
+        MapMetaField::Name,
+        MapMetaField::Author,
+        MapMetaField::Enemy(0),
+        MapMetaField::Enemy(1),
+        MapMetaField::Enemy(2),
+        MapMetaField::TimeLimit,
+        MapMetaField::DropRate,
+        MapMetaField::Music,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            MapMetaField::Name => "editor.meta.name",
+            MapMetaField::Author => "editor.meta.author",
+            MapMetaField::Enemy(0) => "editor.meta.enemy1",
+            MapMetaField::Enemy(1) => "editor.meta.enemy2",
+            MapMetaField::Enemy(_) => "editor.meta.enemy3",
+            MapMetaField::TimeLimit => "editor.meta.time_limit",
+            MapMetaField::DropRate => "editor.meta.drop_rate",
+            MapMetaField::Music => "editor.meta.music",
+        }
+    }
+    ///用文字输入框编辑的项,其它项用左右按钮调整
+    pub fn is_text(&self) -> bool {
+        matches!(
+            self,
+            MapMetaField::Name | MapMetaField::Author | MapMetaField::Music
+        )
+    }
+}
+///地图信息面板中显示值的文字
+#[derive(Component)]
+pub struct MapMetaText(pub MapMetaField);
+///地图信息面板中的文字输入框,点击后开始输入
+#[derive(Component)]
+pub struct MapMetaInput(pub MapMetaField);
+///地图信息面板中调整数值的按钮,值为调整的方向
+#[derive(Component)]
+pub struct MapMetaStep(pub MapMetaField, pub i32);
+///地图信息面板中分享代码的输入框
+#[derive(Component)]
+pub struct MapCodeInput;
+#[derive(Component)]
+pub struct MapCodeText;
+///生成当前地图的分享代码,或把输入框中的代码添加为新地图
+#[derive(Component, Clone, Copy, Eq, PartialEq)]
+pub enum MapCodeButton {
+    Copy,
+    Paste,
+}
+///编辑器左侧面板中的地图列表,新建或删除地图后重建
+#[derive(Component)]
+pub struct EditorLevelList;
+///地图列表中地图名称的文字,值为地图index
+#[derive(Component)]
+pub struct MapNameText(pub usize);
+///编辑器中的弹窗,打开时不能绘制地图
+#[derive(Component)]
+pub struct EditorConfirm;
+///确认弹窗中的按钮,true为确认
+#[derive(Component, Clone, Copy)]
+pub struct EditorConfirmButton(pub bool);
+///编辑器中保存结果的提示,计时结束后消失
+#[derive(Component)]
+pub struct EditorToast(pub Timer);
+///菜单中选择的游戏模式
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameMode {
+    #[default]
+    OnePlayer,
+    TwoPlayers,
+}
+
+impl GameMode {
+    ///参与游戏的玩家数
+    pub fn players(&self) -> usize {
+        match self {
+            GameMode::OnePlayer => 1,
+            GameMode::TwoPlayers => 2,
+        }
+    }
+}
+
 #[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
 pub enum GameDirection {
     #[default]
@@ -35,6 +252,34 @@ pub enum GameDirection {
     Right,
 }
 
+impl GameDirection {
+    ///与KeysBinding::keys()中方向的顺序一致
+    pub const ALL: [GameDirection; 4] = [
+        GameDirection::Up,
+        GameDirection::Down,
+        GameDirection::Left,
+        GameDirection::Right,
+    ];
+}
+///玩家使用的输入设备,手柄的值为bevy中的Gamepad id
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub enum InputDevice {
+    #[default]
+    Keyboard,
+    Gamepad(usize),
+}
+
+impl InputDevice {
+    ///按键设置中依次切换:键盘、手柄1、手柄2
+    pub fn next(&self) -> Self {
+        match self {
+            InputDevice::Keyboard => InputDevice::Gamepad(0),
+            InputDevice::Gamepad(0) => InputDevice::Gamepad(1),
+            InputDevice::Gamepad(_) => InputDevice::Keyboard,
+        }
+    }
+}
+
 #[derive(Component, Clone)]
 pub struct Moving {
     pub direction: GameDirection,
@@ -84,6 +329,15 @@ impl Bullet {
             },
         }
     }
+
+    ///发射子弹的玩家序号,敌人发射的子弹为None
+    pub fn player_slot(&self) -> Option<usize> {
+        match self.index {
+            6 => Some(0),
+            7 => Some(1),
+            _ => None,
+        }
+    }
 }
 #[derive(Clone)]
 pub enum EnemyState {
@@ -105,34 +359,37 @@ pub struct Player {
     pub direction_stack: Vec<GameDirection>,
     pub fire: bool,
     pub keys_binding: Option<KeysBinding>,
+    pub device: InputDevice,
     pub last_turn_direction: Option<GameDirection>,
     pub bullet: Option<Entity>,
     pub shoot_time: Duration,
 }
 
 impl Player {
-    pub fn new_player1() -> Self {
+    pub fn new_player1(keys_binding: KeysBinding, device: InputDevice) -> Self {
         Player {
             id: gen_id(),
             index: 6,
             level: 1,
             fire: false,
             direction_stack: vec![],
-            keys_binding: Some(PLAYER1_KEYS),
+            keys_binding: Some(keys_binding),
+            device,
             last_turn_direction: None,
             bullet: None,
             shoot_time: Duration::from_secs(0),
         }
     }
 
-    pub fn new_player2() -> Self {
+    pub fn new_player2(keys_binding: KeysBinding, device: InputDevice) -> Self {
         Player {
             id: gen_id(),
             index: 7,
             level: 1,
             fire: false,
             direction_stack: vec![],
-            keys_binding: Some(PLAYER2_KEYS),
+            keys_binding: Some(keys_binding),
+            device,
             last_turn_direction: None,
             bullet: None,
             shoot_time: Duration::from_secs(0),
@@ -147,12 +404,22 @@ impl Player {
             fire: false,
             direction_stack: vec![],
             keys_binding: None,
+            device: InputDevice::Keyboard,
             last_turn_direction: None,
             bullet: None,
             shoot_time: Duration::from_secs(0),
         }
     }
 
+    ///玩家序号(0:玩家1, 1:玩家2),敌人为None
+    pub fn player_slot(&self) -> Option<usize> {
+        match self.index {
+            6 => Some(0),
+            7 => Some(1),
+            _ => None,
+        }
+    }
+
     pub fn is_player1(&self) -> bool {
         self.index == 6
     }
@@ -291,8 +558,143 @@ impl NodeBlock {
         }
     }
 }
+///关卡幕布的上下两块
+#[derive(Component)]
+pub struct StageCurtain;
+///关卡幕布中间的文字,计时结束后自动开始游戏
+#[derive(Component)]
+pub struct StageText(pub Timer);
+///地图上掉落的道具
+#[derive(Component, Clone, Copy)]
+pub struct Food(pub PowerUp);
+///道具加固的老家围墙,道具失效后恢复为砖墙
+#[derive(Component)]
+pub struct HomeWall;
+///游戏结束的提示,计时结束后返回菜单
+#[derive(Component)]
+pub struct GameOverText(pub Timer);
+///暂停菜单的根节点
+#[derive(Component)]
+pub struct PauseMenu;
+///暂停菜单的选项,值为选项的序号
+#[derive(Component, Clone, Copy)]
+pub struct PauseMenuItem(pub usize);
+///按键设置中的按键,值为(行号, 玩家序号),动作之后依次是输入设备和返回按钮
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub struct KeysSettingItem(pub usize, pub usize);
+///设置界面中的选项,值为行号,最后一行为返回按钮
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub struct SettingsItem(pub usize);
+///地图包选择界面中的选项,值为地图包序号,最后一行为返回按钮
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub struct MapPackItem(pub usize);
+///显示帧率的文字
+#[derive(Component)]
+pub struct FpsText;
+///需要翻译的文字,值为翻译键和所在的section
+#[derive(Component, Clone)]
+pub struct Tr {
+    pub key: String,
+    pub section: usize,
+}
+
+impl Tr {
+    pub fn new(key: impl Into<String>) -> Self {
+        Self::section(key, 0)
+    }
+
+    pub fn section(key: impl Into<String>, section: usize) -> Self {
+        Tr {
+            key: key.into(),
+            section,
+        }
+    }
+}
+///文字各section原本的字体,切换语言时用于还原
+#[derive(Component)]
+pub struct TextFonts(pub Vec<Handle<Font>>);
+///按键设置中的提示文字
+#[derive(Component)]
+pub struct KeysSettingTip;
+///HUD中需要更新的文字
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum HudText {
+    Stage,
+    Lives(usize),
+    Score(usize),
+    PowerUp,
+    Time,
+}
+///HUD中剩余敌人的图标,值为图标的序号
+#[derive(Component, Clone, Copy)]
+pub struct HudEnemyIcon(pub usize);
+///HUD中当前道具的图标
+#[derive(Component)]
+pub struct HudPowerUp;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PowerUp {
+    Boom,
+    Clock,
+    Gun,
+    Iron,
+    Protect,
+    Star,
+    Tank,
+}
+
+impl PowerUp {
+    pub const ALL: [PowerUp; 7] = [
+        PowerUp::Boom,
+        PowerUp::Clock,
+        PowerUp::Gun,
+        PowerUp::Iron,
+        PowerUp::Protect,
+        PowerUp::Star,
+        PowerUp::Tank,
+    ];
+    ///道具图片在GameSource.foods中的序号
+    pub fn to_index(&self) -> usize {
+        *self as usize
+    }
+    ///有持续时间的道具
+    pub fn duration(&self) -> Option<Duration> {
+        match self {
+            PowerUp::Clock => Some(Duration::from_secs(10)),
+            PowerUp::Iron => Some(Duration::from_secs(15)),
+            PowerUp::Protect => Some(Duration::from_secs(10)),
+            _ => None,
+        }
+    }
+    ///对双方玩家都生效的道具,其余只对拾取的玩家生效
+    pub fn is_shared(&self) -> bool {
+        matches!(self, PowerUp::Boom | PowerUp::Clock | PowerUp::Iron)
+    }
+}
 /* -----------Component--------------- */
 
+/* ---------------Event--------------- */
+///游戏过程中产生的事件, player为玩家序号(0:玩家1, 1:玩家2)
+#[derive(Event, Clone, Copy, Debug)]
+pub enum GameEvent {
+    EnemyDestroyed {
+        player: Option<usize>,
+        enemy: usize,
+    },
+    PlayerDestroyed {
+        player: usize,
+    },
+    PowerUp {
+        player: usize,
+        power_up: PowerUp,
+    },
+    HomeDestroyed,
+}
+///热重载替换了地图,编辑器收到后重建地图列表和编辑区域
+#[derive(Event, Clone, Copy, Debug)]
+pub struct MapReloaded;
+/* ---------------Event--------------- */
+
 /* ---------------Const--------------- */
 pub struct InitialSettings {
     ///窗口设置
@@ -300,7 +702,7 @@ pub struct InitialSettings {
     pub win_resolution: (f32, f32),
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Deserialize, Serialize)]
 pub struct KeysBinding {
     pub up: KeyCode,
     pub down: KeyCode,
@@ -309,6 +711,23 @@ pub struct KeysBinding {
     pub fire: KeyCode,
 }
 
+impl KeysBinding {
+    ///按KEYS_ACTION_LIST的顺序返回按键
+    pub fn keys(&self) -> [KeyCode; 5] {
+        [self.up, self.down, self.left, self.right, self.fire]
+    }
+
+    pub fn set_key(&mut self, index: usize, key: KeyCode) {
+        match index {
+            0 => self.up = key,
+            1 => self.down = key,
+            2 => self.left = key,
+            3 => self.right = key,
+            _ => self.fire = key,
+        }
+    }
+}
+
 pub const INITIAL_SETTINGS: InitialSettings = InitialSettings {
     win_resolution: (1080., 720.),
     win_title: "Tank War",
@@ -330,28 +749,182 @@ pub const PLAYER2_KEYS: KeysBinding = KeysBinding {
     fire: KeyCode::NumpadEnter,
 };
 
-pub const MENU_LIST: [&str; 3] = ["1 player", "2 players", "editor"];
+pub const MENU_LIST: [&str; 5] = [
+    "menu.one_player",
+    "menu.two_players",
+    "menu.editor",
+    "menu.controls",
+    "menu.settings",
+];
+pub const PAUSE_MENU_LIST: [&str; 3] = ["pause.resume", "pause.restart", "common.back_menu"];
+pub const KEYS_ACTION_LIST: [&str; 5] = [
+    "keys.up",
+    "keys.down",
+    "keys.left",
+    "keys.right",
+    "keys.fire",
+];
 pub const GAME_SIZE: Vec2 = Vec2::new(630., 630.);
 pub const GAME_BLOCK_SIZE: (usize, usize) = (24, 24);
 pub const GAME_LOGO_SIZE: (f32, f32) = (450., 120.);
 pub const GAME_MENU_TEXT_SIZE: f32 = 22.0;
 pub const GAME_ICON_ARROW_LEFT: &'static str = "\u{e7f9}";
 pub const GAME_ICON_ARROW_DOWN: &'static str = "\u{e873}";
+pub const GAME_ICON_FLAG: &str = "\u{e60e}";
 pub const GAME_AREA_BLOCK: [usize; 5] = [1, 2, 3, 4, 5];
 pub const GAME_AREA_BLOCK_FOUR: [usize; 6] = [6, 7, 8, 9, 10, 11];
+///每关的敌人总数
+pub const GAME_ENEMY_TOTAL: usize = 20;
+///玩家初始生命数
+pub const GAME_PLAYER_LIVES: usize = 3;
+///场上同时存在的最多敌人数
+pub const GAME_ENEMY_MAX_ALIVE: usize = 4;
+///敌人出生的间隔
+pub const GAME_ENEMY_SPAWN_INTERVAL: Duration = Duration::from_secs(3);
+///过关后进入下一关的等待时间
+pub const GAME_STAGE_CLEAR_DELAY: Duration = Duration::from_secs(3);
+///设置中可选的分辨率
+pub const GAME_RESOLUTION_LIST: [(f32, f32); 3] = [(1080., 720.), (1280., 800.), (1440., 960.)];
+///设置中可选的窗口模式
+pub const GAME_WINDOW_MODE_LIST: [WindowMode; 3] = [
+    WindowMode::Windowed,
+    WindowMode::BorderlessFullscreen,
+    WindowMode::Fullscreen,
+];
+///设置中可选的语言
+pub const GAME_LANGUAGE_LIST: [&str; 2] = ["en", "zh"];
+pub const GAME_VOLUME_MAX: usize = 10;
+pub const SETTINGS_LIST: [&str; 8] = [
+    "settings.resolution",
+    "settings.window",
+    "settings.master_volume",
+    "settings.music_volume",
+    "settings.effect_volume",
+    "settings.difficulty",
+    "settings.show_fps",
+    "settings.language",
+];
+///手柄摇杆的死区
+pub const GAME_GAMEPAD_DEADZONE: f32 = 0.3;
+///地图名称的最大长度
+pub const GAME_MAP_NAME_MAX: usize = 12;
+///地图中每种敌人的最大数量
+pub const GAME_MAP_ENEMY_MAX: usize = 20;
+///地图时间限制的调整间隔和最大值(秒)
+pub const GAME_MAP_TIME_LIMIT_STEP: u32 = 30;
+pub const GAME_MAP_TIME_LIMIT_MAX: u32 = 600;
+///编辑器每张地图最多保留的撤销步数
+pub const GAME_EDITOR_HISTORY_MAX: usize = 100;
+///编辑器图案库中砖块和铁块的常用形状,1为有块的格子,顺序为左上、右上、左下、右下
+pub const GAME_EDITOR_PATTERNS: [[usize; 4]; 9] = [
+    [1, 1, 1, 1],
+    [1, 1, 0, 0],
+    [0, 0, 1, 1],
+    [1, 0, 1, 0],
+    [0, 1, 0, 1],
+    [1, 0, 0, 0],
+    [0, 1, 0, 0],
+    [0, 0, 1, 0],
+    [0, 0, 0, 1],
+];
+///编辑器提示显示的时间
+pub const GAME_EDITOR_TOAST_TIME: Duration = Duration::from_secs(3);
+///地图文件格式的版本,没有version字段的旧文件为0
+pub const GAME_MAP_VERSION: u32 = 1;
+///地图文件的位置,作为内置地图包
+pub const GAME_MAP_PATH: &str = "assets/map.ron";
+///随游戏发布的地图包目录,目录中每个.ron文件是一个地图包
+pub const GAME_MAP_PACK_PATH: &str = "assets/maps";
+///用户地图包在系统数据目录中的位置
+pub const GAME_USER_MAP_PACK_PATH: &str = "bevy_tank/maps";
+///编辑器保存的用户地图包文件名
+pub const GAME_USER_MAP_PACK_FILE: &str = "user.ron";
+///保存地图时保留的备份数量,备份文件为map.ron.bak1、map.ron.bak2...
+pub const GAME_MAP_BACKUP_COUNT: usize = 3;
+///游戏设置的存档位置
+pub const GAME_SETTINGS_PATH: &str = "assets/settings.ron";
+///编辑器图章库的存档位置
+pub const GAME_STAMPS_PATH: &str = "assets/stamps.ron";
+///语言文件所在的目录,文件名为语言代码
+pub const GAME_I18N_PATH: &str = "assets/i18n";
+///通关进度的存档位置
+pub const GAME_PROGRESS_PATH: &str = "assets/progress.ron";
+///玩家被击毁后重生的等待时间
+pub const GAME_PLAYER_RESPAWN_DELAY: Duration = Duration::from_secs(2);
+///玩家出生时的保护时间
+pub const GAME_PLAYER_SPAWN_PROTECT: Duration = Duration::from_secs(3);
+pub const GAME_PLAYER_MAX_LEVEL: usize = 3;
+///玩家击毁敌人时掉落道具的默认概率(百分比),每张地图可以单独设置
+pub const GAME_POWER_UP_DROP_RATE: u32 = 20;
+///游戏结束提示的显示时间
+pub const GAME_OVER_DELAY: Duration = Duration::from_secs(3);
+///击毁敌人(9,10,11)的得分
+pub const GAME_ENEMY_SCORE: [usize; 3] = [100, 200, 300];
 /* ---------------Const--------------- */
 
 /* -----------Resource--------------- */
 ///全局游戏设置信息
-#[derive(Resource)]
+#[derive(Resource, Clone, Deserialize, Serialize)]
+#[serde(default)]
 pub struct GameSettings {
     pub keys_binding: (KeysBinding, KeysBinding),
+    pub devices: (InputDevice, InputDevice),
+    pub resolution: (f32, f32),
+    pub window_mode: WindowMode,
+    pub volume: GameVolume,
+    pub difficulty: Difficulty,
+    pub show_fps: bool,
+    pub language: String,
 }
 
 impl Default for GameSettings {
     fn default() -> Self {
         GameSettings {
             keys_binding: (PLAYER1_KEYS, PLAYER2_KEYS),
+            devices: Default::default(),
+            resolution: INITIAL_SETTINGS.win_resolution,
+            window_mode: WindowMode::Windowed,
+            volume: Default::default(),
+            difficulty: Default::default(),
+            show_fps: false,
+            language: String::from(GAME_LANGUAGE_LIST[0]),
+        }
+    }
+}
+///音量,范围0到GAME_VOLUME_MAX
+#[derive(Clone, Copy, Deserialize, Serialize)]
+pub struct GameVolume {
+    pub master: usize,
+    pub music: usize,
+    pub effect: usize,
+}
+
+impl Default for GameVolume {
+    fn default() -> Self {
+        Self {
+            master: GAME_VOLUME_MAX,
+            music: GAME_VOLUME_MAX,
+            effect: GAME_VOLUME_MAX,
+        }
+    }
+}
+///游戏难度,影响敌人开火的频率和增援的间隔
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    pub fn enemy_spawn_interval(&self) -> Duration {
+        match self {
+            Difficulty::Easy => GAME_ENEMY_SPAWN_INTERVAL * 2,
+            Difficulty::Normal => GAME_ENEMY_SPAWN_INTERVAL,
+            Difficulty::Hard => GAME_ENEMY_SPAWN_INTERVAL / 2,
         }
     }
 }
@@ -370,6 +943,10 @@ pub struct GameSource {
     pub blocks: Vec<Handle<Image>>,
     #[asset(key = "bullets", collection(typed))]
     pub bullets: Vec<Handle<Image>>,
+    #[asset(key = "foods", collection(typed))]
+    pub foods: Vec<Handle<Image>>,
+    #[asset(key = "gameover")]
+    pub gameover: Handle<Image>,
     #[asset(key = "font")]
     pub font: Handle<Font>,
     #[asset(key = "font_mono")]
@@ -377,13 +954,196 @@ pub struct GameSource {
     #[asset(key = "font_icon")]
     pub font_icon: Handle<Font>,
 }
+///地图数据不合法的原因
+#[derive(Debug, Error)]
+pub enum MapError {
+    #[error("map name is empty")]
+    EmptyName,
+    #[error("map size must be 26x26")]
+    Size,
+    #[error("unknown block {block} at ({row}, {col})")]
+    UnknownBlock {
+        row: usize,
+        col: usize,
+        block: usize,
+    },
+    #[error("block {block} at ({row}, {col}) is not a complete 2x2 block")]
+    BrokenBlock {
+        row: usize,
+        col: usize,
+        block: usize,
+    },
+    #[error("block {block} must appear exactly once, found {count}")]
+    UniqueBlock { block: usize, count: usize },
+    #[error("map has no enemy spawn")]
+    NoEnemy,
+}
+
 #[derive(Deserialize, Serialize, Clone)]
 pub struct GameMap {
+    ///读取时检查行列数和块的编号,错误带有位置
+    #[serde(deserialize_with = "deserialize_grid")]
     pub map: Vec<Vec<usize>>,
     pub name: String,
+    //以下为地图信息,旧的地图文件中没有时使用默认值
+    #[serde(default)]
+    pub author: String,
+    ///三种敌人的数量,全为0时数量为GAME_ENEMY_TOTAL,类型由出生点决定
+    #[serde(default)]
+    pub enemy_roster: [usize; 3],
+    ///时间限制(秒),0为不限时
+    #[serde(default)]
+    pub time_limit: u32,
+    ///掉落道具的概率(百分比)
+    #[serde(default = "default_power_up_drop_rate")]
+    pub power_up_drop_rate: u32,
+    ///背景音乐的key
+    #[serde(default)]
+    pub music: String,
+}
+
+fn default_power_up_drop_rate() -> u32 {
+    GAME_POWER_UP_DROP_RATE
 }
 
 impl GameMap {
+    ///空白地图,底部中间为老家和两侧的玩家出生点,顶部为三个敌人出生点
+    pub fn new_blank(name: impl Into<String>) -> Self {
+        let mut map = vec![vec![0; 26]; 26];
+        for (row, col, block) in [
+            (24, 12, 6),
+            (24, 8, 7),
+            (24, 16, 8),
+            (0, 0, 9),
+            (0, 12, 10),
+            (0, 24, 11),
+        ] {
+            for (r, c) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+                map[row + r][col + c] = block;
+            }
+        }
+        //老家周围的砖墙
+        for (row, col) in [
+            (23, 11),
+            (23, 12),
+            (23, 13),
+            (23, 14),
+            (24, 11),
+            (25, 11),
+            (24, 14),
+            (25, 14),
+        ] {
+            map[row][col] = 1;
+        }
+        GameMap {
+            map,
+            name: name.into(),
+            author: String::new(),
+            enemy_roster: [0; 3],
+            time_limit: 0,
+            power_up_drop_rate: GAME_POWER_UP_DROP_RATE,
+            music: String::new(),
+        }
+    }
+    ///本关敌人的总数
+    pub fn enemy_total(&self) -> usize {
+        match self.enemy_roster.iter().sum() {
+            0 => GAME_ENEMY_TOTAL,
+            total => total,
+        }
+    }
+    ///第spawned个出场的敌人类型,按设置的数量依次出场,没有设置时为None
+    pub fn enemy_type(&self, spawned: usize) -> Option<usize> {
+        let mut count = 0;
+        for (i, n) in self.enemy_roster.iter().enumerate() {
+            count += n;
+            if spawned < count {
+                return Some(9 + i);
+            }
+        }
+        None
+    }
+    ///地图信息中的文字项
+    pub fn meta_text(&self, field: MapMetaField) -> Option<&str> {
+        match field {
+            MapMetaField::Name => Some(&self.name),
+            MapMetaField::Author => Some(&self.author),
+            MapMetaField::Music => Some(&self.music),
+            _ => None,
+        }
+    }
+
+    pub fn meta_text_mut(&mut self, field: MapMetaField) -> Option<&mut String> {
+        match field {
+            MapMetaField::Name => Some(&mut self.name),
+            MapMetaField::Author => Some(&mut self.author),
+            MapMetaField::Music => Some(&mut self.music),
+            _ => None,
+        }
+    }
+    ///调整地图信息中的数值项
+    pub fn step_meta(&mut self, field: MapMetaField, step: i32) {
+        let add = |value: u32, delta: i32, max: u32| value.saturating_add_signed(delta).min(max);
+        match field {
+            MapMetaField::Enemy(i) => {
+                self.enemy_roster[i] =
+                    add(self.enemy_roster[i] as u32, step, GAME_MAP_ENEMY_MAX as u32) as usize;
+            }
+            MapMetaField::TimeLimit => {
+                let delta = step * GAME_MAP_TIME_LIMIT_STEP as i32;
+                self.time_limit = add(self.time_limit, delta, GAME_MAP_TIME_LIMIT_MAX);
+            }
+            MapMetaField::DropRate => {
+                self.power_up_drop_rate = add(self.power_up_drop_rate, step * 5, 100);
+            }
+            _ => {}
+        }
+    }
+
+    ///检查地图能否正常加载:大小、方块编号、2x2方块完整,老家和玩家出生点唯一且至少有一个敌人出生点
+    pub fn validate(&self) -> Result<(), MapError> {
+        if self.name.trim().is_empty() {
+            return Err(MapError::EmptyName);
+        }
+        if self.map.len() != 26 || self.map.iter().any(|row| row.len() != 26) {
+            return Err(MapError::Size);
+        }
+        let mut visited = vec![vec![false; 26]; 26];
+        let mut count = [0; 12];
+        for row in 0..26 {
+            for col in 0..26 {
+                let block = self.map[row][col];
+                if block > 11 {
+                    return Err(MapError::UnknownBlock { row, col, block });
+                }
+                if block < 3 || visited[row][col] {
+                    continue;
+                }
+                //与to_blocks一致,从左上角开始占用2x2的格子
+                for (r, c) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+                    let (r, c) = (row + r, col + c);
+                    if r > 25 || c > 25 || visited[r][c] || self.map[r][c] != block {
+                        return Err(MapError::BrokenBlock { row, col, block });
+                    }
+                    visited[r][c] = true;
+                }
+                count[block] += 1;
+            }
+        }
+        for block in [6, 7, 8] {
+            if count[block] != 1 {
+                return Err(MapError::UniqueBlock {
+                    block,
+                    count: count[block],
+                });
+            }
+        }
+        if count[9..].iter().all(|count| *count == 0) {
+            return Err(MapError::NoEnemy);
+        }
+        Ok(())
+    }
+
     pub fn to_blocks(&self) -> Vec<Block> {
         let mut blocks: Vec<Block> = vec![];
         let mut stick = vec![];
@@ -440,22 +1200,117 @@ impl GameMap {
     }
 }
 
-#[derive(Asset, TypePath, Resource, Default, Deserialize, Serialize, Clone)]
+#[derive(Asset, TypePath, Resource, Deserialize, Serialize, Clone)]
 pub struct GameMapCollection {
+    ///文件格式的版本,加载时旧版本的文件升级到GAME_MAP_VERSION
+    #[serde(default)]
+    pub version: u32,
+    ///地图包的名称、作者和版本,旧的地图文件没有这些字段
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub pack_version: String,
     pub maps: Vec<GameMap>,
 }
-///加载地图资源，加载完成后就没有了
+
+impl Default for GameMapCollection {
+    fn default() -> Self {
+        Self {
+            version: GAME_MAP_VERSION,
+            name: Default::default(),
+            author: Default::default(),
+            pack_version: Default::default(),
+            maps: Default::default(),
+        }
+    }
+}
+
+impl GameMapCollection {
+    ///调整关卡顺序,把地图from移动到to
+    pub fn move_map(&mut self, from: usize, to: usize) {
+        let map = self.maps.remove(from);
+        self.maps.insert(to, map);
+    }
+}
+///地图包的来源
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum MapPackSource {
+    ///assets/map.ron
+    BuiltIn,
+    ///assets/maps目录
+    Shipped,
+    ///系统数据目录,编辑器保存到这里
+    User,
+}
+
+#[derive(Clone)]
+pub struct MapPack {
+    ///用于区分通关进度,内置地图包为空
+    pub id: String,
+    pub path: PathBuf,
+    pub source: MapPackSource,
+    pub maps: GameMapCollection,
+}
+
+impl MapPack {
+    ///没有名称时内置和用户地图包显示翻译文字,其它显示文件名
+    pub fn title(&self, locale: &Locale) -> String {
+        if !self.maps.name.is_empty() {
+            return self.maps.name.clone();
+        }
+        match self.source {
+            MapPackSource::BuiltIn => locale.get("pack.built_in"),
+            MapPackSource::User => locale.get("pack.user"),
+            MapPackSource::Shipped => self.id.clone(),
+        }
+    }
+}
+
+///全部地图包,current对应的地图包复制到GameMapCollection中使用
+#[derive(Resource, Default)]
+pub struct MapPacks {
+    pub packs: Vec<MapPack>,
+    pub current: usize,
+}
+
+impl MapPacks {
+    pub fn current(&self) -> &MapPack {
+        &self.packs[self.current]
+    }
+
+    pub fn user_index(&self) -> Option<usize> {
+        self.packs.iter().position(|pack| {
+            pack.source == MapPackSource::User && pack.path.ends_with(GAME_USER_MAP_PACK_FILE)
+        })
+    }
+}
+///加载地图资源,加载完成后保留,热重载时根据它判断是哪个文件变化
 #[derive(Resource)]
 pub struct HandleLoadMap(pub Handle<GameMapCollection>);
 ///界面中选择的信息
 #[derive(Resource)]
 pub struct UISelectInfo {
     pub menu: usize,                   //菜单index
+    pub pause_menu: usize,             //暂停菜单index
     pub map_index: usize,              //游戏选择的地图index
+    pub stage_select: bool,            //关卡幕布中是否可以选择关卡
+    pub game_mode: GameMode,           //菜单选择的游戏模式
+    pub keys_setting: (usize, usize),  //按键设置选择的(动作序号, 玩家序号)
+    pub keys_listening: bool,          //按键设置是否在等待按下新按键
+    pub settings_menu: usize,          //设置界面选择的行
+    pub map_pack: usize,               //地图包界面选择的行
     pub map_editor_level_index: usize, //地图编辑器选择的地图index
     pub map_editor_block: usize,       //地图编辑器选择的块index
     pub map_editor_blocks_inner: [[usize; 4]; 2],
     pub map_editor_cursor: (usize, usize), //地图编辑器的光标位置大方块的 (row, col)
+    pub map_editor_cell: (usize, usize),   //地图编辑器的光标位置小方块的 (row, col)
+    pub map_editor_renaming: bool,         //地图编辑器是否在编辑地图名称
+    pub map_editor_tool: EditorTool,       //地图编辑器选择的绘制工具
+    pub map_editor_mirror: EditorMirror,   //地图编辑器画笔的镜像模式
+    pub map_editor_input: Option<MapMetaField>, //地图信息面板中正在输入的项
+    pub map_editor_analysis: bool,         //地图编辑器是否显示可达性分析
     pub show_line: bool,                   //是否显示光标的边框
 }
 
@@ -463,15 +1318,133 @@ impl Default for UISelectInfo {
     fn default() -> Self {
         Self {
             menu: Default::default(),
+            pause_menu: Default::default(),
             map_index: Default::default(),
+            stage_select: Default::default(),
+            game_mode: Default::default(),
+            keys_setting: Default::default(),
+            keys_listening: Default::default(),
+            settings_menu: Default::default(),
+            map_pack: Default::default(),
             map_editor_level_index: Default::default(),
             map_editor_block: Default::default(),
             map_editor_blocks_inner: [[1; 4], [2; 4]],
             map_editor_cursor: Default::default(),
+            map_editor_cell: Default::default(),
+            map_editor_renaming: Default::default(),
+            map_editor_tool: Default::default(),
+            map_editor_mirror: Default::default(),
+            map_editor_input: Default::default(),
+            map_editor_analysis: false,
             show_line: Default::default(),
         }
     }
 }
+///游戏进行中的记录,HUD根据它显示
+#[derive(Resource)]
+pub struct GameRecord {
+    pub stage: usize,
+    pub enemy_remain: usize,
+    pub lives: [usize; 2],
+    pub score: [usize; 2],
+    pub level: [usize; 2],
+    pub power_up: Option<(PowerUp, Timer)>, //双方共享的道具效果
+    pub player_power_up: [Option<(PowerUp, Timer)>; 2], //玩家各自的道具效果
+    pub difficulty: Difficulty,             //开始新游戏时从设置中读取
+    pub time_limit: Option<Duration>,       //本关剩余时间,None为不限时
+}
+
+impl Default for GameRecord {
+    fn default() -> Self {
+        Self {
+            stage: 0,
+            enemy_remain: GAME_ENEMY_TOTAL,
+            lives: [GAME_PLAYER_LIVES; 2],
+            score: [0; 2],
+            level: [1; 2],
+            power_up: None,
+            player_power_up: [None, None],
+            difficulty: Default::default(),
+            time_limit: None,
+        }
+    }
+}
+
+impl GameRecord {
+    ///共享的道具效果是否生效
+    pub fn is_active(&self, power_up: PowerUp) -> bool {
+        matches!(self.power_up, Some((p, _)) if p == power_up)
+    }
+    ///玩家是否处于保护状态
+    pub fn is_protected(&self, player: usize) -> bool {
+        matches!(self.player_power_up[player], Some((PowerUp::Protect, _)))
+    }
+    ///HUD显示的道具,优先显示共享的道具
+    pub fn active_power_up(&self) -> Option<&(PowerUp, Timer)> {
+        self.power_up
+            .as_ref()
+            .or_else(|| self.player_power_up.iter().flatten().next())
+    }
+}
+///菜单的导航输入,合并了键盘和所有手柄,每个字段只在按下的那一帧为true
+#[derive(Resource, Default)]
+pub struct MenuInput {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub confirm: bool,
+    pub back: bool,
+    pub stick: [bool; 4], //上一帧摇杆按住的方向,用于判断摇杆刚推下
+}
+///当前语言的文字表,font为该语言需要的字体,为None时使用原字体
+#[derive(Resource, Default)]
+pub struct Locale {
+    pub language: String,
+    pub strings: HashMap<String, String>,
+    pub font: Option<Handle<Font>>,
+}
+
+impl Locale {
+    ///找不到翻译时直接显示翻译键
+    pub fn get(&self, key: &str) -> String {
+        self.strings
+            .get(key)
+            .cloned()
+            .unwrap_or_else(|| key.to_string())
+    }
+}
+///通关进度,解锁的关卡可以在关卡幕布中选择;unlocked为内置地图包的进度,其它地图包按id记录
+#[derive(Resource, Default, Deserialize, Serialize, Clone)]
+pub struct GameProgress {
+    pub unlocked: usize,
+    #[serde(default)]
+    pub packs: HashMap<String, usize>,
+}
+
+impl GameProgress {
+    pub fn unlocked(&self, pack: &MapPack) -> usize {
+        if pack.source == MapPackSource::BuiltIn {
+            self.unlocked
+        } else {
+            self.packs.get(&pack.id).copied().unwrap_or_default()
+        }
+    }
+    ///解锁关卡,进度有变化时返回true
+    pub fn unlock(&mut self, pack: &MapPack, index: usize) -> bool {
+        let unlocked = if pack.source == MapPackSource::BuiltIn {
+            &mut self.unlocked
+        } else {
+            self.packs.entry(pack.id.clone()).or_default()
+        };
+        if index > *unlocked {
+            *unlocked = index;
+            true
+        } else {
+            false
+        }
+    }
+}
 #[derive(Resource, Default)]
 pub struct LastSelectInfo {
     pub last_map_editor_block: Option<Entity>,
@@ -479,4 +1452,340 @@ pub struct LastSelectInfo {
     pub last_spawn_block: Vec<Block>, //地图编辑器点击后上次生成的块 左上位置的块
 }
 
+///编辑器中一个格子的修改,after的operate记录是添加、删除还是替换
+#[derive(Clone, Copy, Debug)]
+pub struct EditorChange {
+    pub before: Block,
+    pub after: Block,
+}
+
+impl EditorChange {
+    pub fn new(row: usize, col: usize, before: usize, after: usize) -> Self {
+        let mut after_block = Block::new(row, col, after);
+        after_block.operate = if before == 0 {
+            BlockOperate::Add
+        } else if after == 0 {
+            BlockOperate::Remove
+        } else {
+            BlockOperate::Change
+        };
+        EditorChange {
+            before: Block::new(row, col, before),
+            after: after_block,
+        }
+    }
+}
+///可以撤销的编辑操作
+#[derive(Clone, Debug)]
+pub enum EditorAction {
+    Blocks(Vec<EditorChange>),
+    Rename { before: String, after: String },
+}
+
+impl EditorAction {
+    ///比较修改前后的地图得到格子的修改,没有变化时返回None
+    pub fn diff(before: &[Vec<usize>], after: &[Vec<usize>]) -> Option<Self> {
+        let mut changes = vec![];
+        for (r, (row_before, row_after)) in before.iter().zip(after).enumerate() {
+            for (c, (b, a)) in row_before.iter().zip(row_after).enumerate() {
+                if b != a {
+                    changes.push(EditorChange::new(r, c, *b, *a));
+                }
+            }
+        }
+        if changes.is_empty() {
+            None
+        } else {
+            Some(EditorAction::Blocks(changes))
+        }
+    }
+    ///把操作应用到地图上,undo为true时还原到操作前
+    pub fn apply(&self, game_map: &mut GameMap, undo: bool) {
+        match self {
+            EditorAction::Blocks(changes) => {
+                for change in changes.iter() {
+                    let block = if undo { change.before } else { change.after };
+                    game_map.map[block.row][block.col] = block.block;
+                }
+            }
+            EditorAction::Rename { before, after } => {
+                game_map.name = if undo { before } else { after }.clone();
+            }
+        }
+    }
+}
+///单张地图的操作记录,每个操作带有记录时的序号
+#[derive(Default, Clone)]
+pub struct MapHistory {
+    pub undo: VecDeque<(usize, EditorAction)>,
+    pub redo: Vec<(usize, EditorAction)>,
+}
+///地图列表的操作,added为true时是添加了地图(新建、随机生成或粘贴代码),否则是删除了地图;
+///撤销和重做时在添加和删除之间切换,移除地图时保存它当时的内容和操作记录
+#[derive(Clone)]
+pub struct MapListAction {
+    pub added: bool,
+    pub index: usize,
+    pub game_map: GameMap,
+    pub history: MapHistory,
+}
+///编辑器的操作记录,maps和GameMapCollection中的地图一一对应,list为地图列表的操作;
+///撤销和重做时取当前地图和地图列表中序号较大的操作
+#[derive(Resource, Default)]
+pub struct EditorHistory {
+    pub maps: Vec<MapHistory>,
+    pub list_undo: Vec<(usize, MapListAction)>,
+    pub list_redo: Vec<(usize, MapListAction)>,
+    pub seq: usize,
+}
+
+impl EditorHistory {
+    pub fn map(&mut self, index: usize) -> &mut MapHistory {
+        if self.maps.len() <= index {
+            self.maps.resize_with(index + 1, Default::default);
+        }
+        &mut self.maps[index]
+    }
+
+    fn next_seq(&mut self) -> usize {
+        self.seq += 1;
+        self.seq
+    }
+
+    fn insert_map(&mut self, index: usize, history: MapHistory) {
+        if self.maps.len() < index {
+            self.maps.resize_with(index, Default::default);
+        }
+        self.maps.insert(index, history);
+    }
+
+    fn take_map(&mut self, index: usize) -> MapHistory {
+        if index < self.maps.len() {
+            self.maps.remove(index)
+        } else {
+            MapHistory::default()
+        }
+    }
+    ///记录新的操作,超过上限时丢弃最早的操作
+    pub fn push(&mut self, index: usize, action: EditorAction) {
+        let seq = self.next_seq();
+        self.list_redo.clear();
+        let history = self.map(index);
+        history.redo.clear();
+        history.undo.push_back((seq, action));
+        if history.undo.len() > GAME_EDITOR_HISTORY_MAX {
+            history.undo.pop_front();
+        }
+    }
+
+    fn push_list(&mut self, action: MapListAction) {
+        let seq = self.next_seq();
+        self.list_redo.clear();
+        self.list_undo.push((seq, action));
+        if self.list_undo.len() > GAME_EDITOR_HISTORY_MAX {
+            self.list_undo.remove(0);
+        }
+    }
+    ///地图已经添加到index后记录,新地图没有操作记录
+    pub fn push_add(&mut self, index: usize, game_map: &GameMap) {
+        self.insert_map(index, MapHistory::default());
+        self.push_list(MapListAction {
+            added: true,
+            index,
+            game_map: game_map.clone(),
+            history: MapHistory::default(),
+        });
+    }
+    ///地图已经从index删除后记录,它的操作记录一起保存
+    pub fn push_remove(&mut self, index: usize, game_map: GameMap) {
+        let history = self.take_map(index);
+        self.push_list(MapListAction {
+            added: false,
+            index,
+            game_map,
+            history,
+        });
+    }
+    ///撤销当前地图或地图列表中最近的操作,返回之后编辑器要选择的地图
+    pub fn undo(&mut self, index: usize, gm_map: &mut GameMapCollection) -> Option<usize> {
+        let map_seq = self.map(index).undo.back().map(|(seq, _)| *seq);
+        let list_seq = self.list_undo.last().map(|(seq, _)| *seq);
+        if list_seq > map_seq {
+            let (seq, mut action) = self.list_undo.pop()?;
+            let Some(selected) = self.apply_list(&mut action, gm_map, true) else {
+                self.list_undo.push((seq, action));
+                return None;
+            };
+            let seq = self.next_seq();
+            self.list_redo.push((seq, action));
+            Some(selected)
+        } else {
+            let (_, action) = self.map(index).undo.pop_back()?;
+            action.apply(&mut gm_map.maps[index], true);
+            let seq = self.next_seq();
+            self.map(index).redo.push((seq, action));
+            Some(index)
+        }
+    }
+
+    pub fn redo(&mut self, index: usize, gm_map: &mut GameMapCollection) -> Option<usize> {
+        let map_seq = self.map(index).redo.last().map(|(seq, _)| *seq);
+        let list_seq = self.list_redo.last().map(|(seq, _)| *seq);
+        if list_seq > map_seq {
+            let (seq, mut action) = self.list_redo.pop()?;
+            let Some(selected) = self.apply_list(&mut action, gm_map, false) else {
+                self.list_redo.push((seq, action));
+                return None;
+            };
+            let seq = self.next_seq();
+            self.list_undo.push((seq, action));
+            Some(selected)
+        } else {
+            let (_, action) = self.map(index).redo.pop()?;
+            action.apply(&mut gm_map.maps[index], false);
+            let seq = self.next_seq();
+            self.map(index).undo.push_back((seq, action));
+            Some(index)
+        }
+    }
+    ///按撤销或重做添加、删除地图,至少保留一张地图,不能执行时返回None
+    fn apply_list(
+        &mut self,
+        action: &mut MapListAction,
+        gm_map: &mut GameMapCollection,
+        undo: bool,
+    ) -> Option<usize> {
+        let len = gm_map.maps.len();
+        if action.added != undo {
+            let index = action.index.min(len);
+            gm_map.maps.insert(index, action.game_map.clone());
+            self.insert_map(index, std::mem::take(&mut action.history));
+            Some(index)
+        } else if action.index < len && len > 1 {
+            action.game_map = gm_map.maps.remove(action.index);
+            action.history = self.take_map(action.index);
+            Some(action.index.min(len - 2))
+        } else {
+            None
+        }
+    }
+    ///调整关卡顺序时记录跟随地图移动
+    pub fn move_map(&mut self, from: usize, to: usize) {
+        let history = std::mem::take(self.map(from));
+        self.map(to);
+        self.maps.remove(from);
+        self.maps.insert(to, history);
+        for (_, action) in self.list_undo.iter_mut().chain(self.list_redo.iter_mut()) {
+            action.index = moved_index(action.index, from, to);
+        }
+    }
+}
+///编辑器中的试玩,map为试玩的地图快照,不放进地图列表;有快照时结束后回到编辑器
+#[derive(Resource, Default)]
+pub struct EditorTestPlay {
+    pub map: Option<GameMap>,
+}
+
+impl EditorTestPlay {
+    pub fn active(&self) -> bool {
+        self.map.is_some()
+    }
+
+    ///游戏中正在玩的地图,试玩时是快照,否则是地图列表中选择的地图
+    pub fn game_map<'a>(&'a self, gm_map: &'a GameMapCollection, map_index: usize) -> &'a GameMap {
+        self.map.as_ref().unwrap_or(&gm_map.maps[map_index])
+    }
+
+    ///游戏结束或退出时返回的界面
+    pub fn exit_state(&self) -> GameState {
+        if self.active() {
+            GameState::UIMapEditor
+        } else {
+            GameState::UIMenu
+        }
+    }
+}
+///编辑器复制的区域,格子按行保存,四格块不会被复制
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct EditorStamp {
+    pub name: String,
+    pub map: Vec<Vec<usize>>,
+}
+
+impl EditorStamp {
+    ///复制地图中start到end两个大方块围成的区域
+    pub fn from_map(map: &[Vec<usize>], start: (usize, usize), end: (usize, usize)) -> Self {
+        let (r0, r1) = (start.0.min(end.0) * 2, start.0.max(end.0) * 2 + 1);
+        let (c0, c1) = (start.1.min(end.1) * 2, start.1.max(end.1) * 2 + 1);
+        let map = map[r0..=r1]
+            .iter()
+            .map(|row| {
+                row[c0..=c1]
+                    .iter()
+                    .map(|b| {
+                        if GAME_AREA_BLOCK_FOUR.contains(b) {
+                            0
+                        } else {
+                            *b
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        EditorStamp {
+            name: String::new(),
+            map,
+        }
+    }
+    ///按大方块遍历,返回相对的(row, col)和四个格子
+    pub fn quads(&self) -> impl Iterator<Item = ((usize, usize), [usize; 4])> + '_ {
+        let (rows, cols) = (self.map.len() / 2, self.map.first().map_or(0, Vec::len) / 2);
+        (0..rows).flat_map(move |r| {
+            (0..cols).map(move |c| {
+                let cells = [0, 1, 2, 3].map(|i| self.map[r * 2 + i / 2][c * 2 + i % 2]);
+                ((r, c), cells)
+            })
+        })
+    }
+
+    pub fn mirror_horizontal(&mut self) {
+        for row in self.map.iter_mut() {
+            row.reverse();
+        }
+    }
+
+    pub fn mirror_vertical(&mut self) {
+        self.map.reverse();
+    }
+    ///顺时针旋转90°
+    pub fn rotate(&mut self) {
+        let rows = self.map.len();
+        let cols = self.map.first().map_or(0, Vec::len);
+        self.map = (0..cols)
+            .map(|c| (0..rows).rev().map(|r| self.map[r][c]).collect())
+            .collect();
+    }
+}
+///编辑器中框选的区域,起点和终点为大方块的(行, 列)
+#[derive(Clone, Copy)]
+pub struct Selection {
+    pub map_index: usize,
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+}
+///编辑器的剪贴板
+#[derive(Resource, Default)]
+pub struct EditorClipboard {
+    pub selection: Option<Selection>,
+    pub drag_start: Option<(usize, usize)>,
+    pub stamp: Option<EditorStamp>,
+    pub pasting: bool,
+}
+///保存在GAME_STAMPS_PATH中的图章库
+#[derive(Resource, Deserialize, Serialize, Default)]
+pub struct EditorStampLibrary {
+    pub stamps: Vec<EditorStamp>,
+    #[serde(skip)]
+    pub index: usize,
+}
 /* -----------Resource--------------- */
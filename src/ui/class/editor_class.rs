@@ -2,7 +2,7 @@ use crate::{
     res::{GameSource, NodeBlock},
     utils::animate::{Animator, LoopStrategy},
 };
-use bevy::prelude::*;
+use bevy::{prelude::*, ui::FocusPolicy};
 use std::time::Duration;
 
 pub fn class_node_left_panel(mut style: Mut<Style>, mut background_color: Mut<BackgroundColor>) {
@@ -24,6 +24,173 @@ pub fn class_node_right_panel(mut style: Mut<Style>, mut background_color: Mut<B
     background_color.0 = Color::rgb_u8(30, 34, 41);
 }
 
+pub fn class_node_level_list(mut style: Mut<Style>) {
+    style.width = Val::Percent(100.);
+    style.display = Display::Flex;
+    style.flex_direction = FlexDirection::Column;
+}
+
+pub fn class_node_confirm_mask(
+    mut style: Mut<Style>,
+    mut background_color: Mut<BackgroundColor>,
+    mut z_index: Mut<ZIndex>,
+    mut focus_policy: Mut<FocusPolicy>,
+) {
+    style.position_type = PositionType::Absolute;
+    style.width = Val::Percent(100.);
+    style.height = Val::Percent(100.);
+    style.display = Display::Flex;
+    style.align_items = AlignItems::Center;
+    style.justify_content = JustifyContent::Center;
+    background_color.0 = Color::rgba(0., 0., 0., 0.6);
+    *z_index = ZIndex::Global(10);
+    *focus_policy = FocusPolicy::Block;
+}
+
+pub fn class_node_confirm_panel(
+    mut style: Mut<Style>,
+    mut border_color: Mut<BorderColor>,
+    mut background_color: Mut<BackgroundColor>,
+) {
+    style.width = Val::Px(320.);
+    style.display = Display::Flex;
+    style.flex_direction = FlexDirection::Column;
+    style.align_items = AlignItems::Center;
+    style.border = UiRect::all(Val::Px(3.));
+    style.padding = UiRect::all(Val::Px(15.));
+    border_color.0 = Color::rgb(0.6, 0.6, 0.6);
+    background_color.0 = Color::rgb_u8(30, 34, 41);
+}
+
+pub fn class_node_confirm_buttons(mut style: Mut<Style>) {
+    style.display = Display::Flex;
+    style.column_gap = Val::Px(20.);
+}
+
+pub fn class_node_toast(
+    mut style: Mut<Style>,
+    mut background_color: Mut<BackgroundColor>,
+    mut z_index: Mut<ZIndex>,
+) {
+    style.position_type = PositionType::Absolute;
+    style.bottom = Val::Px(30.);
+    style.left = Val::Percent(50.);
+    style.width = Val::Px(400.);
+    style.margin.left = Val::Px(-200.);
+    style.display = Display::Flex;
+    style.justify_content = JustifyContent::Center;
+    style.padding = UiRect::all(Val::Px(10.));
+    background_color.0 = Color::rgba_u8(40, 120, 60, 230);
+    *z_index = ZIndex::Global(20);
+}
+
+pub fn class_node_toast_error(mut background_color: Mut<BackgroundColor>) {
+    background_color.0 = Color::rgba_u8(160, 40, 40, 230);
+}
+
+pub fn class_node_meta_panel(mut style: Mut<Style>) {
+    style.width = Val::Px(420.);
+    style.row_gap = Val::Px(6.);
+}
+
+pub fn class_node_meta_row(mut style: Mut<Style>) {
+    style.width = Val::Percent(100.);
+    style.height = Val::Px(30.);
+    style.display = Display::Flex;
+    style.align_items = AlignItems::Center;
+    style.justify_content = JustifyContent::SpaceBetween;
+}
+
+pub fn class_node_meta_input(mut style: Mut<Style>) {
+    style.width = Val::Px(200.);
+    style.height = Val::Px(28.);
+    style.display = Display::Flex;
+    style.align_items = AlignItems::Center;
+    style.padding.left = Val::Px(6.);
+    style.overflow = Overflow::clip();
+}
+
+pub fn class_node_meta_stepper(mut style: Mut<Style>) {
+    style.width = Val::Px(200.);
+    style.display = Display::Flex;
+    style.align_items = AlignItems::Center;
+    style.justify_content = JustifyContent::SpaceBetween;
+}
+
+pub fn class_node_meta_step_btn(mut style: Mut<Style>) {
+    style.width = Val::Px(28.);
+    style.height = Val::Px(28.);
+    style.display = Display::Flex;
+    style.align_items = AlignItems::Center;
+    style.justify_content = JustifyContent::Center;
+}
+
+pub fn class_node_meta_code(mut style: Mut<Style>) {
+    style.width = Val::Percent(100.);
+    style.min_height = Val::Px(64.);
+    style.padding = UiRect::all(Val::Px(6.));
+    style.overflow = Overflow::clip();
+}
+
+pub fn class_node_meta_code_text(mut style: Mut<Style>, mut text: Mut<Text>) {
+    style.max_width = Val::Px(390.);
+    text.sections[0].style.font_size = 11.0;
+    text.sections[0].style.color = Color::WHITE;
+}
+
+pub fn class_node_level_drop_target(mut background_color: Mut<BackgroundColor>) {
+    background_color.0 = Color::rgb_u8(60, 110, 170);
+}
+
+pub fn class_node_analysis(mut style: Mut<Style>) {
+    style.position_type = PositionType::Absolute;
+    style.top = Val::Px(6.);
+    style.left = Val::Percent(50.);
+    style.width = Val::Px(624.);
+    style.margin.left = Val::Px(-312.);
+    style.display = Display::Flex;
+    style.justify_content = JustifyContent::Center;
+}
+
+pub fn class_node_analysis_text(mut text: Mut<Text>) {
+    for section in text.sections.iter_mut() {
+        section.style.font_size = 16.0;
+        section.style.color = Color::WHITE;
+    }
+    text.sections[2].style.color = Color::rgb_u8(230, 80, 80);
+    text.justify = JustifyText::Center;
+}
+
+pub fn class_node_tool_list(mut style: Mut<Style>) {
+    style.width = Val::Px(130.);
+    style.display = Display::Flex;
+    style.flex_wrap = FlexWrap::Wrap;
+    style.justify_content = JustifyContent::SpaceBetween;
+    style.margin.top = Val::Px(15.);
+}
+
+pub fn class_node_tool_btn(mut style: Mut<Style>) {
+    style.width = Val::Px(62.);
+    style.height = Val::Px(30.);
+    style.display = Display::Flex;
+    style.align_items = AlignItems::Center;
+    style.justify_content = JustifyContent::Center;
+    style.margin.top = Val::Px(6.);
+}
+
+pub fn class_node_tool_btn_default(mut background_color: Mut<BackgroundColor>) {
+    background_color.0 = Color::rgb_u8(90, 90, 90);
+}
+
+pub fn class_node_tool_btn_select(mut background_color: Mut<BackgroundColor>) {
+    background_color.0 = Color::rgb_u8(180, 60, 60);
+}
+
+pub fn class_node_tool_btn_text(mut text: Mut<Text>) {
+    text.sections[0].style.font_size = 16.0;
+    text.sections[0].style.color = Color::WHITE;
+}
+
 pub fn class_node_menu_btn(mut style: Mut<Style>, mut background_color: Mut<BackgroundColor>) {
     style.width = Val::Px(120.);
     style.height = Val::Px(40.);
@@ -121,6 +288,29 @@ pub fn class_node_map_name_text(
     text.justify = JustifyText::Left;
 }
 
+pub fn class_node_pattern(
+    mut style: Mut<Style>,
+    mut border_color: Mut<BorderColor>,
+    mut background_color: Mut<BackgroundColor>,
+) {
+    style.width = Val::Px(32.);
+    style.height = Val::Px(32.);
+    style.display = Display::Flex;
+    style.flex_wrap = FlexWrap::Wrap;
+    style.border = UiRect::all(Val::Px(4.));
+    border_color.0 = Color::rgb_u8(90, 90, 90);
+    background_color.0 = Color::BLACK;
+}
+
+pub fn class_node_pattern_cell(mut style: Mut<Style>) {
+    style.width = Val::Px(12.);
+    style.height = Val::Px(12.);
+}
+
+pub fn class_node_pattern_cell_on(mut background_color: Mut<BackgroundColor>) {
+    background_color.0 = Color::rgb_u8(156, 74, 0);
+}
+
 pub fn class_wd_node_block_size(mut style: Mut<Style>) {
     style.width = Val::Px(52.);
     style.height = Val::Px(52.);
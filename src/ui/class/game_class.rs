@@ -1,7 +1,8 @@
 use bevy::prelude::*;
 
 use crate::res::{
-    Block, Bullet, GameDirection, GameSource, Moving, GAME_AREA_BLOCK, GAME_BLOCK_SIZE, GAME_SIZE,
+    Block, Bullet, Food, GameDirection, GameSource, Moving, GAME_AREA_BLOCK, GAME_BLOCK_SIZE,
+    GAME_SIZE,
 };
 
 pub fn class_sprite_panel(
@@ -91,3 +92,28 @@ pub fn class_sprite_bullet(
         }
     }
 }
+
+pub fn class_sprite_food(
+    mut image: Mut<Handle<Image>>,
+    mut sprite: Mut<Sprite>,
+    mut transform: Mut<Transform>,
+    food: Mut<Food>,
+    block: Mut<Block>,
+    gm_res: Res<GameSource>,
+) {
+    *image = gm_res.foods[food.0.to_index()].clone();
+    sprite.custom_size = Some(Vec2::new(32., 32.));
+    let (x, y) = block.to_pos();
+    transform.translation = Vec3::new(x, y, 5.);
+}
+
+pub fn class_sprite_game_over(
+    mut image: Mut<Handle<Image>>,
+    mut sprite: Mut<Sprite>,
+    mut transform: Mut<Transform>,
+    gm_res: Res<GameSource>,
+) {
+    *image = gm_res.gameover.clone();
+    sprite.custom_size = Some(Vec2::new(128., 64.));
+    transform.translation.z = 10.;
+}
@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+
+use crate::res::{GameSource, GAME_SIZE};
+
+pub fn class_node_hud_panel(mut style: Mut<Style>, mut background_color: Mut<BackgroundColor>) {
+    //从窗口中线偏移半个游戏区域,宽度随窗口大小变化
+    style.height = Val::Percent(100.);
+    style.position_type = PositionType::Absolute;
+    style.left = Val::Percent(50.);
+    style.margin.left = Val::Px(GAME_SIZE.x / 2.);
+    style.right = Val::Px(0.);
+    style.display = Display::Flex;
+    style.flex_direction = FlexDirection::Column;
+    style.align_items = AlignItems::Center;
+    style.padding.top = Val::Px(40.);
+    background_color.0 = Color::rgb_u8(30, 34, 41);
+}
+
+pub fn class_node_hud_enemy_grid(mut style: Mut<Style>) {
+    style.width = Val::Px(60.);
+    style.height = Val::Px(240.);
+    style.margin.bottom = Val::Px(30.);
+}
+
+pub fn class_node_hud_enemy_icon(
+    mut style: Mut<Style>,
+    mut layout: Mut<TextureAtlas>,
+    mut image: Mut<UiImage>,
+    gm_res: Res<GameSource>,
+) {
+    style.width = Val::Px(20.);
+    style.height = Val::Px(20.);
+    layout.layout = gm_res.layout_tank.clone();
+    image.texture = gm_res.blocks[8].clone();
+}
+
+pub fn class_node_hud_item(mut style: Mut<Style>) {
+    style.width = Val::Px(150.);
+    style.display = Display::Flex;
+    style.flex_direction = FlexDirection::Row;
+    style.align_items = AlignItems::Center;
+    style.margin.bottom = Val::Px(20.);
+}
+
+pub fn class_node_hud_text(mut text: Mut<Text>, gm_res: Res<GameSource>) {
+    for section in text.sections.iter_mut() {
+        section.style.font = gm_res.font.clone();
+        section.style.font_size = 16.0;
+        section.style.color = Color::WHITE;
+    }
+}
+
+pub fn class_node_hud_icon_text(mut text: Mut<Text>, gm_res: Res<GameSource>) {
+    text.sections[0].style.font = gm_res.font_icon.clone();
+    text.sections[0].style.font_size = 22.0;
+    text.sections[0].style.color = Color::ORANGE;
+}
+
+pub fn class_node_hud_power_up(mut style: Mut<Style>, mut visibility: Mut<Visibility>) {
+    style.width = Val::Px(32.);
+    style.height = Val::Px(32.);
+    style.margin.right = Val::Px(10.);
+    *visibility = Visibility::Hidden;
+}
@@ -0,0 +1,40 @@
+use bevy::prelude::*;
+
+use crate::{res::GameSource, utils::widget::GridItemInfo};
+
+pub fn class_node_keys_grid(mut style: Mut<Style>) {
+    style.width = Val::Px(450.);
+    style.height = Val::Px(360.);
+    style.display = Display::Flex;
+    style.align_items = AlignItems::Center;
+}
+
+pub fn class_node_keys_item(mut style: Mut<Style>, grid_item_info: Mut<GridItemInfo>) {
+    style.width = Val::Px(if grid_item_info.1 == 0 { 130. } else { 150. });
+    style.justify_content = JustifyContent::Center;
+    style.padding.top = Val::Px(8.);
+}
+
+pub fn class_node_keys_text(mut style: Mut<Style>, mut text: Mut<Text>, gm_res: Res<GameSource>) {
+    text.sections[0].style.font = gm_res.font.clone();
+    text.sections[0].style.font_size = 18.;
+    text.sections[0].style.color = Color::WHITE;
+    text.justify = JustifyText::Center;
+    style.width = Val::Px(120.);
+    style.height = Val::Px(26.);
+}
+
+pub fn class_node_keys_title(mut style: Mut<Style>, mut text: Mut<Text>, gm_res: Res<GameSource>) {
+    text.sections[0].style.font = gm_res.font.clone();
+    text.sections[0].style.font_size = 28.;
+    text.sections[0].style.color = Color::WHITE;
+    style.margin.bottom = Val::Px(20.);
+}
+
+pub fn class_node_keys_tip(mut style: Mut<Style>, mut text: Mut<Text>, gm_res: Res<GameSource>) {
+    text.sections[0].style.font = gm_res.font.clone();
+    text.sections[0].style.font_size = 16.;
+    text.sections[0].style.color = Color::rgb(0.9, 0.3, 0.3);
+    style.margin.top = Val::Px(15.);
+    style.height = Val::Px(20.);
+}
@@ -1,6 +1,12 @@
 pub mod menu_class;
 pub mod game_class;
 pub mod editor_class;
+pub mod hud_class;
+pub mod pause_class;
+pub mod stage_class;
+pub mod keys_class;
+pub mod settings_class;
+pub mod pack_class;
 
 use bevy::prelude::*;
 
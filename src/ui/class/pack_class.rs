@@ -0,0 +1,32 @@
+use bevy::prelude::*;
+
+use crate::res::GameSource;
+
+pub fn class_node_pack_list(mut style: Mut<Style>) {
+    style.display = Display::Flex;
+    style.flex_direction = FlexDirection::Column;
+    style.align_items = AlignItems::Center;
+    style.width = Val::Px(520.);
+    style.max_height = Val::Px(460.);
+    style.overflow = Overflow::clip_y();
+}
+
+pub fn class_node_pack_item(mut style: Mut<Style>) {
+    style.width = Val::Px(500.);
+    style.justify_content = JustifyContent::Center;
+    style.padding = UiRect::axes(Val::Px(10.), Val::Px(8.));
+    style.margin.bottom = Val::Px(4.);
+}
+
+///第一段为地图包名称,第二段为版本、作者和地图数量
+pub fn class_node_pack_text(mut text: Mut<Text>, gm_res: Res<GameSource>) {
+    text.sections[0].style.font = gm_res.font.clone();
+    text.sections[0].style.font_size = 18.;
+    text.sections[0].style.color = Color::WHITE;
+    if let Some(section) = text.sections.get_mut(1) {
+        section.style.font = gm_res.font.clone();
+        section.style.font_size = 12.;
+        section.style.color = Color::rgb(0.6, 0.6, 0.6);
+    }
+    text.justify = JustifyText::Center;
+}
@@ -0,0 +1,33 @@
+use bevy::prelude::*;
+
+pub fn class_node_pause_mask(
+    mut style: Mut<Style>,
+    mut background_color: Mut<BackgroundColor>,
+    mut z_index: Mut<ZIndex>,
+) {
+    style.position_type = PositionType::Absolute;
+    style.width = Val::Percent(100.);
+    style.height = Val::Percent(100.);
+    style.display = Display::Flex;
+    style.align_items = AlignItems::Center;
+    style.justify_content = JustifyContent::Center;
+    background_color.0 = Color::rgba(0., 0., 0., 0.6);
+    *z_index = ZIndex::Global(10);
+}
+
+pub fn class_node_pause_panel(
+    mut style: Mut<Style>,
+    mut border_color: Mut<BorderColor>,
+    mut background_color: Mut<BackgroundColor>,
+) {
+    style.width = Val::Px(300.);
+    style.height = Val::Px(180.);
+    style.border = UiRect::all(Val::Px(3.));
+    style.padding = UiRect::all(Val::Px(15.));
+    border_color.0 = Color::rgb(0.6, 0.6, 0.6);
+    background_color.0 = Color::BLACK;
+}
+
+pub fn class_node_pause_menu_item(mut style: Mut<Style>) {
+    style.padding.top = Val::Px(10.);
+}
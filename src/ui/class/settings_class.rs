@@ -0,0 +1,41 @@
+use bevy::prelude::*;
+
+use crate::{res::GameSource, utils::widget::GridItemInfo};
+
+pub fn class_node_settings_grid(mut style: Mut<Style>) {
+    style.width = Val::Px(440.);
+    style.height = Val::Px(405.);
+    style.display = Display::Flex;
+    style.align_items = AlignItems::Center;
+}
+
+pub fn class_node_settings_item(mut style: Mut<Style>, grid_item_info: Mut<GridItemInfo>) {
+    style.width = Val::Px(if grid_item_info.1 == 0 { 180. } else { 260. });
+    style.justify_content = JustifyContent::Center;
+    style.padding.top = Val::Px(8.);
+}
+
+pub fn class_node_settings_text(
+    mut style: Mut<Style>,
+    mut text: Mut<Text>,
+    gm_res: Res<GameSource>,
+) {
+    text.sections[0].style.font = gm_res.font.clone();
+    text.sections[0].style.font_size = 18.;
+    text.sections[0].style.color = Color::WHITE;
+    text.justify = JustifyText::Center;
+    style.width = Val::Px(240.);
+    style.height = Val::Px(26.);
+}
+
+pub fn class_node_fps(mut style: Mut<Style>, mut z_index: Mut<ZIndex>) {
+    style.position_type = PositionType::Absolute;
+    style.left = Val::Px(5.);
+    style.top = Val::Px(5.);
+    *z_index = ZIndex::Global(20);
+}
+
+pub fn class_node_fps_text(mut text: Mut<Text>) {
+    text.sections[0].style.font_size = 16.;
+    text.sections[0].style.color = Color::YELLOW;
+}
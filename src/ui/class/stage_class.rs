@@ -0,0 +1,59 @@
+use bevy::prelude::*;
+use std::time::Duration;
+
+use crate::{
+    res::GameSource,
+    utils::animate::{Animator, LoopStrategy},
+};
+
+fn class_node_stage_curtain(
+    mut style: Mut<Style>,
+    mut background_color: Mut<BackgroundColor>,
+    mut animator: Mut<Animator>,
+) {
+    style.position_type = PositionType::Absolute;
+    style.left = Val::Px(0.);
+    style.width = Val::Percent(100.);
+    style.height = Val::Percent(0.);
+    background_color.0 = Color::rgb_u8(99, 99, 99);
+    animator
+        .set_loop_strategy(LoopStrategy::Once)
+        .add_change()
+        .set_duration(Duration::from_millis(600))
+        .set_style_size(Val::Auto, Val::Percent(50.));
+}
+
+pub fn class_node_stage_curtain_top(
+    mut style: Mut<Style>,
+    background_color: Mut<BackgroundColor>,
+    animator: Mut<Animator>,
+) {
+    style.top = Val::Px(0.);
+    class_node_stage_curtain(style, background_color, animator);
+}
+
+pub fn class_node_stage_curtain_bottom(
+    mut style: Mut<Style>,
+    background_color: Mut<BackgroundColor>,
+    animator: Mut<Animator>,
+) {
+    style.bottom = Val::Px(0.);
+    class_node_stage_curtain(style, background_color, animator);
+}
+
+pub fn class_node_stage_text(
+    mut style: Mut<Style>,
+    mut text: Mut<Text>,
+    mut visibility: Mut<Visibility>,
+    gm_res: Res<GameSource>,
+) {
+    style.position_type = PositionType::Absolute;
+    style.top = Val::Percent(50.);
+    style.width = Val::Percent(100.);
+    style.margin.top = Val::Px(-14.);
+    text.sections[0].style.font = gm_res.font.clone();
+    text.sections[0].style.font_size = 28.0;
+    text.sections[0].style.color = Color::BLACK;
+    text.justify = JustifyText::Center;
+    *visibility = Visibility::Hidden;
+}
@@ -2,27 +2,50 @@ use bevy::prelude::*;
 
 use crate::{
     res::{
-        Block, BlockOperate, Clear, GameMapCollection, GameState, LastSelectInfo, NodeBlock,
-        RightPanelButton, UISelectInfo, GAME_AREA_BLOCK_FOUR,
+        Block, BlockOperate, Clear, EditorAction, EditorAnalysisText, EditorClipboard,
+        EditorConfirm, EditorConfirmButton, EditorHistory, EditorLevelList, EditorMirror,
+        EditorMirrorText, EditorPattern, EditorStamp, EditorStampLibrary, EditorTestPlay,
+        EditorToast, EditorTool, GameMap, GameMapCollection, GameRecord, GameSettings, GameState,
+        LastSelectInfo, Locale, MapCodeButton, MapCodeInput, MapCodeText, MapMetaField,
+        MapMetaInput, MapMetaStep, MapMetaText, MapNameText, MapPacks, MapReloaded, NodeBlock,
+        RightPanelButton, Selection, Tr, UISelectInfo, GAME_AREA_BLOCK_FOUR, GAME_EDITOR_PATTERNS,
+        GAME_EDITOR_TOAST_TIME, GAME_MAP_NAME_MAX,
     },
     utils::{
         class::StyleCommand,
-        util::{is_four, save_map, vec2_to_transform_pos},
-        widget::{button_children, node_children, node_root, sprite, text, GridItemInfo},
+        generator::MapGenerator,
+        share_code::{decode_map, encode_map},
+        util::{
+            analyze_map, is_four, moved_index, new_user_map_pack, save_map, save_stamps,
+            user_map_pack_path, vec2_to_transform_pos, MapAnalysis,
+        },
+        widget::{
+            button_children, node_children, node_root, node_text, node_text_t, sprite, text,
+            GridItemInfo,
+        },
     },
 };
 
 use super::{
     class::{
-        class_node_fill,
         editor_class::{
-            class_node_collapse_item_default, class_node_collapse_item_hover,
-            class_node_left_panel, class_node_map_name_style, class_node_map_name_text,
-            class_node_menu_btn, class_node_menu_btn_text, class_node_right_panel,
+            class_node_analysis, class_node_analysis_text, class_node_collapse_item_default,
+            class_node_collapse_item_hover, class_node_confirm_buttons, class_node_confirm_mask,
+            class_node_confirm_panel, class_node_left_panel, class_node_level_drop_target,
+            class_node_level_list, class_node_menu_btn, class_node_menu_btn_text,
+            class_node_meta_code, class_node_meta_code_text, class_node_meta_input,
+            class_node_meta_panel, class_node_meta_row, class_node_meta_step_btn,
+            class_node_meta_stepper, class_node_right_panel, class_node_toast,
+            class_node_toast_error, class_node_tool_btn, class_node_tool_btn_default,
+            class_node_tool_btn_select, class_node_tool_btn_text, class_node_tool_list,
+            class_wd_node_block_container_default, class_wd_node_block_container_select,
+            class_wd_node_block_item,
         },
         game_class::{class_sprite_block, class_sprite_sheet_block},
     },
-    widget::{wd_load_game_map, wd_node_block, wd_setup_collapse_grid},
+    widget::{
+        wd_level_list, wd_load_game_map, wd_node_block, wd_node_pattern, wd_setup_collapse_grid,
+    },
 };
 
 pub fn setup_ui_editor(
@@ -32,31 +55,10 @@ pub fn setup_ui_editor(
     mut last_select_info: ResMut<LastSelectInfo>,
 ) {
     node_root(class_node_left_panel, commands.reborrow(), Clear, |gc| {
-        wd_setup_collapse_grid("LEVEL", gm_maps.maps.len(), 1, 30., gc, |gc, r, c| {
-            node_children(
-                (
-                    class_node_fill,
-                    if select_info.map_editor_level_index == r {
-                        class_node_collapse_item_hover
-                    } else {
-                        class_node_collapse_item_default
-                    },
-                ),
-                gc,
-                (Interaction::None, GridItemInfo(r, c)),
-                |gc| {
-                    node_children(class_node_map_name_style, gc, (), |gc| {
-                        text(
-                            [gm_maps.maps[r].name.as_str()],
-                            class_node_map_name_text,
-                            gc,
-                            (),
-                        );
-                    });
-                },
-            );
+        node_children(class_node_level_list, gc, EditorLevelList, |gc| {
+            wd_level_list(gc, &gm_maps, &select_info, false);
         });
-        wd_setup_collapse_grid("BLOCK", 6, 2, 75., gc, |gc, r, c| {
+        wd_setup_collapse_grid("editor.block", 6, 2, 75., false, gc, |gc, r, c| {
             let index = r * 2 + c;
             if index < 12 {
                 //解决初始化选择时存储
@@ -80,71 +82,120 @@ pub fn setup_ui_editor(
                 }
             }
         });
+        wd_setup_collapse_grid("editor.pattern", 3, 3, 40., false, gc, |gc, r, c| {
+            wd_node_pattern(gc, r * 3 + c);
+        });
+    });
+    node_root(class_node_analysis, commands.reborrow(), Clear, |gc| {
+        text(
+            ["", "\n", ""],
+            class_node_analysis_text,
+            gc,
+            EditorAnalysisText,
+        );
     });
     node_root(class_node_right_panel, commands.reborrow(), Clear, |gc| {
         button_children(class_node_menu_btn, gc, RightPanelButton::NewMap, |gc| {
-            text(
-                ["new map"],
-                class_node_menu_btn_text,
-                gc,
-                (),
-            );
+            node_text_t("editor.new_map", class_node_menu_btn_text, gc, ());
         });
+        button_children(
+            class_node_menu_btn,
+            gc,
+            RightPanelButton::GenerateMap,
+            |gc| {
+                node_text_t("editor.generate_map", class_node_menu_btn_text, gc, ());
+            },
+        );
         button_children(class_node_menu_btn, gc, RightPanelButton::DeleteMap, |gc| {
-            text(
-                ["delete map"],
-                class_node_menu_btn_text,
-                gc,
-                (),
-            );
+            node_text_t("editor.delete_map", class_node_menu_btn_text, gc, ());
+        });
+        node_children(class_node_tool_list, gc, (), |gc| {
+            for (button, key) in [
+                (RightPanelButton::MoveUp, "editor.move_up"),
+                (RightPanelButton::MoveDown, "editor.move_down"),
+            ] {
+                button_children(
+                    (class_node_tool_btn, class_node_tool_btn_default),
+                    gc,
+                    button,
+                    |gc| {
+                        node_text_t(key, class_node_tool_btn_text, gc, ());
+                    },
+                );
+            }
         });
         button_children(class_node_menu_btn, gc, RightPanelButton::SaveMap, |gc| {
-            text(
-                ["save map"],
-                class_node_menu_btn_text,
-                gc,
-                (),
-            );
+            node_text_t("editor.save_map", class_node_menu_btn_text, gc, ());
+        });
+        button_children(class_node_menu_btn, gc, RightPanelButton::TestPlay, |gc| {
+            node_text_t("editor.test_play", class_node_menu_btn_text, gc, ());
+        });
+        button_children(class_node_menu_btn, gc, RightPanelButton::MapInfo, |gc| {
+            node_text_t("editor.map_info", class_node_menu_btn_text, gc, ());
         });
         button_children(class_node_menu_btn, gc, RightPanelButton::Back, |gc| {
-            text(
-                ["back menu"],
+            node_text_t("common.back_menu", class_node_menu_btn_text, gc, ());
+        });
+        button_children(class_node_menu_btn, gc, RightPanelButton::Mirror, |gc| {
+            node_text_t(
+                select_info.map_editor_mirror.key(),
                 class_node_menu_btn_text,
                 gc,
-                (),
+                EditorMirrorText,
             );
         });
+        button_children(class_node_menu_btn, gc, RightPanelButton::Analysis, |gc| {
+            node_text_t("editor.analysis", class_node_menu_btn_text, gc, ());
+        });
+        node_children(class_node_tool_list, gc, (), |gc| {
+            for tool in EditorTool::ALL {
+                let class = if tool == select_info.map_editor_tool {
+                    class_node_tool_btn_select
+                } else {
+                    class_node_tool_btn_default
+                };
+                button_children((class_node_tool_btn, class), gc, tool, |gc| {
+                    node_text_t(tool.key(), class_node_tool_btn_text, gc, ());
+                });
+            }
+        });
     });
 }
 
 pub fn update_ui_editor(
     mut commands: Commands,
     query_event: Query<(&Interaction, &GridItemInfo), Changed<Interaction>>,
-    query_entity: Query<Entity, (With<Interaction>, With<GridItemInfo>)>,
+    query_entity: Query<(Entity, &GridItemInfo), With<Interaction>>,
     gm_map: Res<GameMapCollection>,
     mut ui_selector: ResMut<UISelectInfo>,
     right_panel_button: Query<(&Interaction, &RightPanelButton), Changed<Interaction>>,
     gm_state: Res<State<GameState>>,
     mut next_state: ResMut<NextState<GameState>>,
     gm_panel_entity: Query<Entity, (With<Sprite>, With<Clear>)>,
+    settings: Res<GameSettings>,
 ) {
     let gm_panel = gm_panel_entity.single();
     for (interaction, grid_item) in query_event.iter() {
-        if *interaction == Interaction::Pressed && ui_selector.map_editor_level_index != grid_item.0
+        //再次点击已选择的地图时编辑名称
+        if *interaction == Interaction::Pressed && ui_selector.map_editor_level_index == grid_item.0
         {
-            println!("选择地图:{}", grid_item.0);
+            ui_selector.map_editor_renaming = true;
+        } else if *interaction == Interaction::Pressed {
+            ui_selector.map_editor_renaming = false;
+            println!("select map: {}", grid_item.0);
             ui_selector.map_editor_level_index = grid_item.0;
             commands
                 .entity(gm_panel)
                 .despawn_descendants()
                 .with_children(|gc| {
-                    wd_load_game_map(gc, gm_map.as_ref(), &ui_selector, &gm_state);
+                    let game_map = &gm_map.maps[ui_selector.map_editor_level_index];
+                    wd_load_game_map(gc, game_map, &ui_selector, &gm_state, &settings);
                 });
         }
     }
     //优化：存储上次的选择的实体进行更新
-    for (index, entity) in query_entity.iter().enumerate() {
-        if index == ui_selector.map_editor_level_index {
+    for (entity, grid_item) in query_entity.iter() {
+        if grid_item.0 == ui_selector.map_editor_level_index {
             commands.set_style(entity, class_node_collapse_item_hover);
         } else {
             commands.set_style(entity, class_node_collapse_item_default);
@@ -152,38 +203,782 @@ pub fn update_ui_editor(
     }
 
     for (interaction, button) in right_panel_button.iter() {
+        if *interaction == Interaction::Pressed && *button == RightPanelButton::Back {
+            next_state.set(GameState::UIMenu);
+            println!("back menu");
+        }
+    }
+}
+
+///保存地图,结果显示为提示,提示计时结束后消失
+pub fn update_ui_editor_save(
+    mut commands: Commands,
+    right_panel_button: Query<(&Interaction, &RightPanelButton), Changed<Interaction>>,
+    gm_map: Res<GameMapCollection>,
+    mut packs: ResMut<MapPacks>,
+    locale: Res<Locale>,
+    time: Res<Time>,
+    mut query_toast: Query<(Entity, &mut EditorToast)>,
+) {
+    for (entity, mut toast) in query_toast.iter_mut() {
+        if toast.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    let saved = right_panel_button.iter().any(|(interaction, button)| {
+        *interaction == Interaction::Pressed && *button == RightPanelButton::SaveMap
+    });
+    if !saved {
+        return;
+    }
+    let toasts = query_toast.iter().map(|(entity, _)| entity);
+    //保存到用户地图包,不覆盖随游戏发布的地图
+    match save_map(&user_map_pack_path(), &gm_map) {
+        Ok(()) => {
+            if let Some(index) = packs.user_index() {
+                packs.packs[index].maps.clone_from(&gm_map);
+            }
+            spawn_toast(
+                &mut commands,
+                toasts,
+                locale.get("editor.save_success"),
+                false,
+            )
+        }
+        Err(err) => {
+            let message = format!("{}: {}", locale.get("editor.save_failed"), err);
+            spawn_toast(&mut commands, toasts, message, true);
+        }
+    }
+}
+
+///显示新的提示并移除旧的提示,error为true时显示为错误
+fn spawn_toast(
+    commands: &mut Commands,
+    old: impl IntoIterator<Item = Entity>,
+    message: String,
+    error: bool,
+) {
+    for entity in old {
+        commands.entity(entity).despawn_recursive();
+    }
+    let toast = EditorToast(Timer::new(GAME_EDITOR_TOAST_TIME, TimerMode::Once));
+    if error {
+        node_root(
+            (class_node_toast, class_node_toast_error),
+            commands.reborrow(),
+            (Clear, toast),
+            |gc| {
+                node_text(message, class_node_menu_btn_text, gc, ());
+            },
+        );
+    } else {
+        node_root(
+            class_node_toast,
+            commands.reborrow(),
+            (Clear, toast),
+            |gc| {
+                node_text(message, class_node_menu_btn_text, gc, ());
+            },
+        );
+    }
+}
+
+///新建地图、随机生成地图和确认后删除地图,完成后重建地图列表并加载选择的地图
+#[allow(clippy::too_many_arguments)]
+pub fn update_ui_editor_maps(
+    mut commands: Commands,
+    right_panel_button: Query<(&Interaction, &RightPanelButton), Changed<Interaction>>,
+    query_confirm: Query<(&Interaction, &EditorConfirmButton), Changed<Interaction>>,
+    query_dialog: Query<Entity, With<EditorConfirm>>,
+    query_level_list: Query<Entity, With<EditorLevelList>>,
+    gm_panel_entity: Query<Entity, (With<Sprite>, With<Clear>)>,
+    query_toast: Query<Entity, With<EditorToast>>,
+    mut gm_map: ResMut<GameMapCollection>,
+    mut ui_selector: ResMut<UISelectInfo>,
+    gm_state: Res<State<GameState>>,
+    settings: Res<GameSettings>,
+    locale: Res<Locale>,
+    mut history: ResMut<EditorHistory>,
+) {
+    let mut changed = false;
+    for (interaction, button) in right_panel_button.iter() {
+        if *interaction != Interaction::Pressed || !query_dialog.is_empty() {
+            continue;
+        }
+        match *button {
+            RightPanelButton::NewMap | RightPanelButton::GenerateMap => {
+                let name = format!("map{}", gm_map.maps.len() + 1);
+                let game_map = if *button == RightPanelButton::GenerateMap {
+                    let seed = rand::random();
+                    //提示种子,用map_tool generate --seed可以生成同样的地图
+                    let message = format!("{}: {}", locale.get("editor.generate_seed"), seed);
+                    spawn_toast(&mut commands, query_toast.iter(), message, false);
+                    MapGenerator::new(seed).generate(name)
+                } else {
+                    GameMap::new_blank(name)
+                };
+                gm_map.maps.push(game_map);
+                ui_selector.map_editor_level_index = gm_map.maps.len() - 1;
+                ui_selector.map_editor_renaming = true;
+                let index = gm_map.maps.len() - 1;
+                history.push_add(index, &gm_map.maps[index]);
+                changed = true;
+            }
+            //至少保留一张地图
+            RightPanelButton::DeleteMap if gm_map.maps.len() > 1 => {
+                ui_selector.map_editor_renaming = false;
+                let name = &gm_map.maps[ui_selector.map_editor_level_index].name;
+                node_root(
+                    class_node_confirm_mask,
+                    commands.reborrow(),
+                    (Clear, EditorConfirm),
+                    |gc| {
+                        node_children(class_node_confirm_panel, gc, (), |gc| {
+                            node_text(
+                                format!("{} {}?", locale.get("editor.delete_map"), name),
+                                class_node_menu_btn_text,
+                                gc,
+                                (),
+                            );
+                            node_children(class_node_confirm_buttons, gc, (), |gc| {
+                                for (confirm, key) in [(true, "common.yes"), (false, "common.no")]
+                                {
+                                    button_children(
+                                        class_node_menu_btn,
+                                        gc,
+                                        EditorConfirmButton(confirm),
+                                        |gc| {
+                                            node_text_t(key, class_node_menu_btn_text, gc, ());
+                                        },
+                                    );
+                                }
+                            });
+                        });
+                    },
+                );
+            }
+            _ => {}
+        }
+    }
+    for (interaction, button) in query_confirm.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        for entity in query_dialog.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        if button.0 && gm_map.maps.len() > 1 {
+            let index = ui_selector.map_editor_level_index;
+            let removed = gm_map.maps.remove(index);
+            history.push_remove(index, removed);
+            let last = gm_map.maps.len() - 1;
+            ui_selector.map_editor_level_index = index.min(last);
+            //相当于把删除的地图移到最后再去掉,后面的地图前移一位
+            if ui_selector.map_index != index {
+                ui_selector.map_index = moved_index(ui_selector.map_index, index, last + 1);
+            }
+            ui_selector.map_index = ui_selector.map_index.min(last);
+            changed = true;
+        }
+    }
+    if changed {
+        for entity in query_level_list.iter() {
+            reload_level_list(&mut commands, entity, &gm_map, &ui_selector);
+        }
+        for entity in gm_panel_entity.iter() {
+            reload_editor_map(
+                &mut commands,
+                entity,
+                &gm_map,
+                &ui_selector,
+                &gm_state,
+                &settings,
+            );
+        }
+    }
+}
+
+///拖动地图列表中的地图到其它地图上,或点击上移、下移按钮调整关卡顺序
+#[allow(clippy::too_many_arguments)]
+pub fn update_ui_editor_reorder(
+    mut commands: Commands,
+    mouse_event: Res<ButtonInput<MouseButton>>,
+    query_event: Query<(&Interaction, &GridItemInfo), Changed<Interaction>>,
+    query_item: Query<(Entity, &Interaction, &GridItemInfo)>,
+    right_panel_button: Query<(&Interaction, &RightPanelButton), Changed<Interaction>>,
+    query_dialog: Query<(), With<EditorConfirm>>,
+    query_level_list: Query<Entity, With<EditorLevelList>>,
+    mut gm_map: ResMut<GameMapCollection>,
+    mut ui_selector: ResMut<UISelectInfo>,
+    mut history: ResMut<EditorHistory>,
+    mut drag: Local<Option<usize>>,
+) {
+    for (interaction, item) in query_event.iter() {
         if *interaction == Interaction::Pressed {
-            match *button {
-                RightPanelButton::NewMap => {
-                    println!("新建地图");
+            *drag = Some(item.0);
+        }
+    }
+    let index = ui_selector.map_editor_level_index;
+    let mut target = None;
+    if let Some(from) = *drag {
+        //按住时其它地图只会是Hovered,松开时落在哪张地图上就移动到哪里
+        let hovered = query_item
+            .iter()
+            .find(|(_, interaction, item)| item.0 != from && **interaction == Interaction::Hovered);
+        if let Some((entity, _, item)) = hovered {
+            if mouse_event.pressed(MouseButton::Left) {
+                commands.set_style(entity, class_node_level_drop_target);
+            } else {
+                target = Some((from, item.0));
+            }
+        }
+        if !mouse_event.pressed(MouseButton::Left) {
+            *drag = None;
+        }
+    }
+    for (interaction, button) in right_panel_button.iter() {
+        if *interaction != Interaction::Pressed || !query_dialog.is_empty() {
+            continue;
+        }
+        match *button {
+            RightPanelButton::MoveUp if index > 0 => target = Some((index, index - 1)),
+            RightPanelButton::MoveDown if index + 1 < gm_map.maps.len() => {
+                target = Some((index, index + 1))
+            }
+            _ => {}
+        }
+    }
+    let Some((from, to)) = target else {
+        return;
+    };
+    gm_map.move_map(from, to);
+    history.move_map(from, to);
+    ui_selector.map_editor_level_index = moved_index(index, from, to);
+    ui_selector.map_index = moved_index(ui_selector.map_index, from, to);
+    ui_selector.map_editor_renaming = false;
+    for entity in query_level_list.iter() {
+        reload_level_list(&mut commands, entity, &gm_map, &ui_selector);
+    }
+}
+
+///重建地图列表,重建后列表保持展开
+fn reload_level_list(
+    commands: &mut Commands,
+    level_list: Entity,
+    gm_map: &GameMapCollection,
+    ui_selector: &UISelectInfo,
+) {
+    commands
+        .entity(level_list)
+        .despawn_descendants()
+        .with_children(|gc| {
+            wd_level_list(gc, gm_map, ui_selector, true);
+        });
+}
+
+///map.ron或用户地图包热重载后重建地图列表和编辑区域
+#[allow(clippy::too_many_arguments)]
+pub fn update_ui_editor_map_reloaded(
+    mut commands: Commands,
+    mut reloaded: EventReader<MapReloaded>,
+    query_level_list: Query<Entity, With<EditorLevelList>>,
+    gm_panel_entity: Query<Entity, (With<Sprite>, With<Clear>)>,
+    gm_map: Res<GameMapCollection>,
+    ui_selector: Res<UISelectInfo>,
+    gm_state: Res<State<GameState>>,
+    settings: Res<GameSettings>,
+) {
+    if reloaded.read().count() == 0 {
+        return;
+    }
+    for entity in query_level_list.iter() {
+        reload_level_list(&mut commands, entity, &gm_map, &ui_selector);
+    }
+    for entity in gm_panel_entity.iter() {
+        reload_editor_map(
+            &mut commands,
+            entity,
+            &gm_map,
+            &ui_selector,
+            &gm_state,
+            &settings,
+        );
+    }
+}
+
+///按地图数据重新生成编辑区域中的精灵
+fn reload_editor_map(
+    commands: &mut Commands,
+    panel: Entity,
+    gm_map: &GameMapCollection,
+    ui_selector: &UISelectInfo,
+    gm_state: &State<GameState>,
+    settings: &GameSettings,
+) {
+    commands
+        .entity(panel)
+        .despawn_descendants()
+        .with_children(|gc| {
+            let game_map = &gm_map.maps[ui_selector.map_editor_level_index];
+            wd_load_game_map(gc, game_map, ui_selector, gm_state, settings);
+        });
+}
+
+///笔刷按下到松开记录为一次操作,Ctrl+Z撤销,Ctrl+Y或Ctrl+Shift+Z重做
+#[allow(clippy::too_many_arguments)]
+pub fn update_ui_editor_history(
+    mut commands: Commands,
+    key_event: Res<ButtonInput<KeyCode>>,
+    mouse_event: Res<ButtonInput<MouseButton>>,
+    mut gm_map: ResMut<GameMapCollection>,
+    mut history: ResMut<EditorHistory>,
+    mut ui_selector: ResMut<UISelectInfo>,
+    gm_state: Res<State<GameState>>,
+    settings: Res<GameSettings>,
+    gm_panel_entity: Query<Entity, (With<Sprite>, With<Clear>)>,
+    query_level_list: Query<Entity, With<EditorLevelList>>,
+    mut stroke: Local<Option<(usize, Vec<Vec<usize>>)>>,
+) {
+    let index = ui_selector.map_editor_level_index;
+    //在笔刷和工具修改地图之前保存按下时的地图
+    if mouse_event.just_pressed(MouseButton::Left) && ui_selector.show_line {
+        *stroke = Some((index, gm_map.maps[index].map.clone()));
+    }
+    //工具和粘贴在松开的那一帧才修改地图,下一帧再记录
+    if !mouse_event.pressed(MouseButton::Left) && !mouse_event.just_released(MouseButton::Left) {
+        if let Some((stroke_index, before)) = stroke.take() {
+            if let Some(action) = gm_map
+                .maps
+                .get(stroke_index)
+                .and_then(|game_map| EditorAction::diff(&before, &game_map.map))
+            {
+                history.push(stroke_index, action);
+            }
+        }
+    }
+    if ui_selector.map_editor_renaming
+        || stroke.is_some()
+        || !(key_event.pressed(KeyCode::ControlLeft) || key_event.pressed(KeyCode::ControlRight))
+    {
+        return;
+    }
+    let shift = key_event.pressed(KeyCode::ShiftLeft) || key_event.pressed(KeyCode::ShiftRight);
+    let len = gm_map.maps.len();
+    let selected = if key_event.just_pressed(KeyCode::KeyZ) && !shift {
+        history.undo(index, &mut gm_map)
+    } else if key_event.just_pressed(KeyCode::KeyY) || key_event.just_pressed(KeyCode::KeyZ) {
+        history.redo(index, &mut gm_map)
+    } else {
+        None
+    };
+    if let Some(selected) = selected {
+        //添加或删除了地图时选中它所在的位置,并让当前关卡跟着移动
+        if gm_map.maps.len() != len {
+            let map_index = ui_selector.map_index;
+            ui_selector.map_index = if gm_map.maps.len() > len {
+                map_index + usize::from(map_index >= selected)
+            } else {
+                map_index - usize::from(map_index > selected)
+            }
+            .min(gm_map.maps.len() - 1);
+            ui_selector.map_editor_level_index = selected;
+            for entity in query_level_list.iter() {
+                reload_level_list(&mut commands, entity, &gm_map, &ui_selector);
+            }
+        }
+        for entity in gm_panel_entity.iter() {
+            reload_editor_map(
+                &mut commands,
+                entity,
+                &gm_map,
+                &ui_selector,
+                &gm_state,
+                &settings,
+            );
+        }
+    }
+}
+
+///编辑地图名称,Enter或Esc结束
+pub fn update_ui_editor_rename(
+    key_event: Res<ButtonInput<KeyCode>>,
+    mut char_event: EventReader<ReceivedCharacter>,
+    mut gm_map: ResMut<GameMapCollection>,
+    mut ui_selector: ResMut<UISelectInfo>,
+    mut query_name: Query<(&mut Text, &MapNameText)>,
+    mut history: ResMut<EditorHistory>,
+    mut original: Local<Option<(usize, String)>>,
+) {
+    let index = ui_selector.map_editor_level_index;
+    //开始编辑时记下原名称,结束时记录为一次操作
+    if ui_selector.map_editor_renaming && original.is_none() {
+        *original = Some((index, gm_map.maps[index].name.clone()));
+    } else if !ui_selector.map_editor_renaming {
+        if let Some((original_index, before)) = original.take() {
+            if let Some(game_map) = gm_map.maps.get(original_index) {
+                if game_map.name != before {
+                    let after = game_map.name.clone();
+                    history.push(original_index, EditorAction::Rename { before, after });
                 }
-                RightPanelButton::SaveMap => {
-                    save_map(&gm_map);
-                    println!("保存地图");
+            }
+        }
+    }
+    if ui_selector.map_editor_renaming {
+        let name = &mut gm_map.maps[index].name;
+        for event in char_event.read() {
+            for c in event.char.chars().filter(|c| !c.is_control()) {
+                if name.chars().count() < GAME_MAP_NAME_MAX {
+                    name.push(c);
+                }
+            }
+        }
+        if key_event.just_pressed(KeyCode::Backspace) {
+            name.pop();
+        }
+        if key_event.just_pressed(KeyCode::Enter) || key_event.just_pressed(KeyCode::Escape) {
+            ui_selector.map_editor_renaming = false;
+        }
+    } else {
+        char_event.clear();
+    }
+    for (mut text, name_text) in query_name.iter_mut() {
+        let Some(map) = gm_map.maps.get(name_text.0) else {
+            continue;
+        };
+        let value = if ui_selector.map_editor_renaming && name_text.0 == index {
+            format!("{}_", map.name)
+        } else {
+            map.name.clone()
+        };
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
+
+///地图信息面板:名称沿用重命名的输入,作者和音乐点击后输入,数值项用左右按钮调整
+#[allow(clippy::too_many_arguments)]
+pub fn update_ui_editor_meta(
+    mut commands: Commands,
+    key_event: Res<ButtonInput<KeyCode>>,
+    mut char_event: EventReader<ReceivedCharacter>,
+    right_panel_button: Query<(&Interaction, &RightPanelButton), Changed<Interaction>>,
+    query_input: Query<(&Interaction, &MapMetaInput), Changed<Interaction>>,
+    query_input_button: Query<(Entity, &MapMetaInput), With<Button>>,
+    query_step: Query<(&Interaction, &MapMetaStep), Changed<Interaction>>,
+    query_dialog: Query<(), With<EditorConfirm>>,
+    mut query_text: Query<(&mut Text, &MapMetaText)>,
+    mut gm_map: ResMut<GameMapCollection>,
+    mut ui_selector: ResMut<UISelectInfo>,
+    locale: Res<Locale>,
+    mut last_input: Local<Option<MapMetaField>>,
+) {
+    for (interaction, button) in right_panel_button.iter() {
+        if *interaction == Interaction::Pressed
+            && *button == RightPanelButton::MapInfo
+            && query_dialog.is_empty()
+        {
+            ui_selector.map_editor_renaming = false;
+            setup_ui_editor_meta(commands.reborrow());
+        }
+    }
+    //面板关闭或名称输入结束后取消输入
+    let input = ui_selector.map_editor_input;
+    if input.is_some()
+        && (query_dialog.is_empty()
+            || (input == Some(MapMetaField::Name) && !ui_selector.map_editor_renaming))
+    {
+        if input == Some(MapMetaField::Name) {
+            ui_selector.map_editor_renaming = false;
+        }
+        ui_selector.map_editor_input = None;
+    }
+    let index = ui_selector.map_editor_level_index;
+    for (interaction, input) in query_input.iter() {
+        if *interaction == Interaction::Pressed {
+            ui_selector.map_editor_input = Some(input.0);
+            ui_selector.map_editor_renaming = input.0 == MapMetaField::Name;
+        }
+    }
+    for (interaction, step) in query_step.iter() {
+        if *interaction == Interaction::Pressed {
+            gm_map.maps[index].step_meta(step.0, step.1);
+        }
+    }
+    //名称由update_ui_editor_rename输入
+    let typing = ui_selector
+        .map_editor_input
+        .filter(|field| *field != MapMetaField::Name);
+    if let Some(field) = typing {
+        if let Some(value) = gm_map.maps[index].meta_text_mut(field) {
+            for event in char_event.read() {
+                for c in event.char.chars().filter(|c| !c.is_control()) {
+                    if value.chars().count() < GAME_MAP_NAME_MAX {
+                        value.push(c);
+                    }
                 }
-                RightPanelButton::Back => {
-                    next_state.set(GameState::UIMenu);
-                    println!("返回菜单");
+            }
+            if key_event.just_pressed(KeyCode::Backspace) {
+                value.pop();
+            }
+        }
+        if key_event.just_pressed(KeyCode::Enter) || key_event.just_pressed(KeyCode::Escape) {
+            ui_selector.map_editor_input = None;
+        }
+    } else {
+        char_event.clear();
+    }
+    if *last_input != ui_selector.map_editor_input {
+        *last_input = ui_selector.map_editor_input;
+        for (entity, input) in query_input_button.iter() {
+            if Some(input.0) == ui_selector.map_editor_input {
+                commands.set_style(entity, class_node_tool_btn_select);
+            } else {
+                commands.set_style(entity, class_node_tool_btn_default);
+            }
+        }
+    }
+    let map = &gm_map.maps[index];
+    for (mut text, meta_text) in query_text.iter_mut() {
+        let field = meta_text.0;
+        let value = match field {
+            MapMetaField::Enemy(_) if map.enemy_roster.iter().all(|n| *n == 0) => {
+                locale.get("editor.meta.auto")
+            }
+            MapMetaField::Enemy(i) => map.enemy_roster[i].to_string(),
+            MapMetaField::TimeLimit if map.time_limit == 0 => locale.get("editor.meta.off"),
+            MapMetaField::TimeLimit => format!("{}s", map.time_limit),
+            MapMetaField::DropRate => format!("{}%", map.power_up_drop_rate),
+            _ => {
+                let value = map.meta_text(field).unwrap_or_default();
+                if ui_selector.map_editor_input == Some(field) {
+                    format!("{}_", value)
+                } else {
+                    value.to_string()
                 }
-                _ => {}
             }
+        };
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
         }
     }
 }
 
+fn setup_ui_editor_meta(commands: Commands) {
+    node_root(
+        class_node_confirm_mask,
+        commands,
+        (Clear, EditorConfirm),
+        |gc| {
+            node_children(
+                (class_node_confirm_panel, class_node_meta_panel),
+                gc,
+                (),
+                |gc| {
+                    node_text_t("editor.map_info", class_node_menu_btn_text, gc, ());
+                    for field in MapMetaField::ALL {
+                        node_children(class_node_meta_row, gc, (), |gc| {
+                            node_text_t(field.key(), class_node_tool_btn_text, gc, ());
+                            if field.is_text() {
+                                button_children(
+                                    (class_node_meta_input, class_node_tool_btn_default),
+                                    gc,
+                                    MapMetaInput(field),
+                                    |gc| {
+                                        node_text(
+                                            "",
+                                            class_node_tool_btn_text,
+                                            gc,
+                                            MapMetaText(field),
+                                        );
+                                    },
+                                );
+                            } else {
+                                node_children(class_node_meta_stepper, gc, (), |gc| {
+                                    button_children(
+                                        (class_node_meta_step_btn, class_node_tool_btn_default),
+                                        gc,
+                                        MapMetaStep(field, -1),
+                                        |gc| {
+                                            node_text("<", class_node_tool_btn_text, gc, ());
+                                        },
+                                    );
+                                    node_text("", class_node_tool_btn_text, gc, MapMetaText(field));
+                                    button_children(
+                                        (class_node_meta_step_btn, class_node_tool_btn_default),
+                                        gc,
+                                        MapMetaStep(field, 1),
+                                        |gc| {
+                                            node_text(">", class_node_tool_btn_text, gc, ());
+                                        },
+                                    );
+                                });
+                            }
+                        });
+                    }
+                    node_children(class_node_meta_row, gc, (), |gc| {
+                        node_text_t("editor.map_code", class_node_tool_btn_text, gc, ());
+                    });
+                    button_children(
+                        (class_node_meta_code, class_node_tool_btn_default),
+                        gc,
+                        MapCodeInput,
+                        |gc| {
+                            node_text("", class_node_meta_code_text, gc, MapCodeText);
+                        },
+                    );
+                    node_children(class_node_confirm_buttons, gc, (), |gc| {
+                        for (button, key) in [
+                            (MapCodeButton::Copy, "editor.copy_map_code"),
+                            (MapCodeButton::Paste, "editor.paste_map_code"),
+                        ] {
+                            button_children(class_node_menu_btn, gc, button, |gc| {
+                                node_text_t(key, class_node_tool_btn_text, gc, ());
+                            });
+                        }
+                    });
+                    button_children(class_node_menu_btn, gc, EditorConfirmButton(false), |gc| {
+                        node_text_t("common.back", class_node_menu_btn_text, gc, ());
+                    });
+                },
+            );
+        },
+    );
+}
+
+///地图信息面板中的分享代码:复制时把当前地图的代码填入输入框并输出到控制台,粘贴时把输入框中的代码添加为新地图
+#[allow(clippy::too_many_arguments)]
+pub fn update_ui_editor_share_code(
+    mut commands: Commands,
+    (key_event, mut char_event): (Res<ButtonInput<KeyCode>>, EventReader<ReceivedCharacter>),
+    query_input: Query<(Entity, &Interaction, &MapCodeInput), Changed<Interaction>>,
+    query_button: Query<(&Interaction, &MapCodeButton), Changed<Interaction>>,
+    mut query_text: Query<&mut Text, With<MapCodeText>>,
+    query_toast: Query<Entity, With<EditorToast>>,
+    query_level_list: Query<Entity, With<EditorLevelList>>,
+    gm_panel_entity: Query<Entity, (With<Sprite>, With<Clear>)>,
+    mut gm_map: ResMut<GameMapCollection>,
+    mut ui_selector: ResMut<UISelectInfo>,
+    mut history: ResMut<EditorHistory>,
+    (locale, gm_state, settings): (Res<Locale>, Res<State<GameState>>, Res<GameSettings>),
+    mut code: Local<String>,
+    mut typing: Local<bool>,
+) {
+    //面板关闭或开始输入其它项时结束输入
+    if query_text.is_empty() || ui_selector.map_editor_input.is_some() {
+        *typing = false;
+    }
+    for (entity, interaction, _) in query_input.iter() {
+        if *interaction == Interaction::Pressed {
+            *typing = true;
+            ui_selector.map_editor_input = None;
+            ui_selector.map_editor_renaming = false;
+            commands.set_style(entity, class_node_tool_btn_select);
+        } else if !*typing {
+            commands.set_style(entity, class_node_tool_btn_default);
+        }
+    }
+    let mut pasted = false;
+    for (interaction, button) in query_button.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match *button {
+            MapCodeButton::Copy => {
+                let game_map = &gm_map.maps[ui_selector.map_editor_level_index];
+                *code = encode_map(game_map);
+                let message = locale.get("editor.map_code_copied");
+                spawn_toast(&mut commands, query_toast.iter(), message, false);
+            }
+            MapCodeButton::Paste => pasted = true,
+        }
+    }
+    if *typing {
+        for event in char_event.read() {
+            let chars = event.char.chars();
+            code.extend(chars.filter(|c| c.is_ascii_alphanumeric() || "-_=".contains(*c)));
+        }
+        if key_event.just_pressed(KeyCode::Backspace) {
+            code.pop();
+        }
+        if key_event.just_pressed(KeyCode::Enter) {
+            pasted = true;
+        }
+        if key_event.just_pressed(KeyCode::Enter) || key_event.just_pressed(KeyCode::Escape) {
+            *typing = false;
+        }
+    } else {
+        char_event.clear();
+    }
+    if pasted {
+        match decode_map(&code) {
+            Ok(game_map) => {
+                gm_map.maps.push(game_map);
+                let index = gm_map.maps.len() - 1;
+                ui_selector.map_editor_level_index = index;
+                history.push_add(index, &gm_map.maps[index]);
+                for entity in query_level_list.iter() {
+                    reload_level_list(&mut commands, entity, &gm_map, &ui_selector);
+                }
+                for entity in gm_panel_entity.iter() {
+                    reload_editor_map(
+                        &mut commands,
+                        entity,
+                        &gm_map,
+                        &ui_selector,
+                        &gm_state,
+                        &settings,
+                    );
+                }
+                let message = locale.get("editor.map_code_pasted");
+                spawn_toast(&mut commands, query_toast.iter(), message, false);
+            }
+            Err(err) => {
+                let message = format!("{}: {}", locale.get("editor.map_code_failed"), err);
+                spawn_toast(&mut commands, query_toast.iter(), message, true);
+            }
+        }
+    }
+    for mut text in query_text.iter_mut() {
+        let value = if *typing {
+            format!("{}_", *code)
+        } else {
+            code.clone()
+        };
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn update_ui_editor_brush(
     mut commands: Commands,
     mouse_event: Res<ButtonInput<MouseButton>>,
+    key_event: Res<ButtonInput<KeyCode>>,
     mut ui_selector: ResMut<UISelectInfo>,
     mut move_event: EventReader<CursorMoved>,
     mut gizmos: Gizmos,
     mut query_block: Query<(&mut Block, Entity)>,
     mut gm_maps: ResMut<GameMapCollection>,
     gm_panel_entity: Query<Entity, (With<Sprite>, With<Clear>)>,
+    query_dialog: Query<(), With<EditorConfirm>>,
+    query_window: Query<&Window>,
+    (gm_state, settings): (Res<State<GameState>>, Res<GameSettings>),
 ) {
+    //确认弹窗打开时不绘制
+    if !query_dialog.is_empty() {
+        move_event.clear();
+        return;
+    }
     let panel_entity = gm_panel_entity.single();
     for evt in move_event.read() {
-        let transform_pos = vec2_to_transform_pos(evt.position);
+        let Ok(window) = query_window.get(evt.window) else {
+            continue;
+        };
+        let window_size = Vec2::new(window.width(), window.height());
+        let transform_pos = vec2_to_transform_pos(evt.position, window_size);
         if transform_pos.0 > -312.
             && transform_pos.1 > -312.
             && transform_pos.1 < 312.
@@ -193,17 +988,38 @@ pub fn update_ui_editor_brush(
                 ((312. - transform_pos.1) / 48.) as usize,
                 ((transform_pos.0 + 312.) / 48.) as usize,
             );
+            ui_selector.map_editor_cell = (
+                ((312. - transform_pos.1) / 24.) as usize,
+                ((transform_pos.0 + 312.) / 24.) as usize,
+            );
             ui_selector.show_line = true;
         } else {
             ui_selector.show_line = false;
         }
     }
+    //按住Shift时画笔对齐到小方块,只绘制一个格子,只支持空白、砖块和铁块
+    let precise = (key_event.pressed(KeyCode::ShiftLeft) || key_event.pressed(KeyCode::ShiftRight))
+        && ui_selector.map_editor_tool == EditorTool::Pencil
+        && [0, 1, 2].contains(&ui_selector.map_editor_block);
+    //镜像轴
+    let mirror = ui_selector.map_editor_mirror;
+    if mirror.flips().0 {
+        gizmos.line_2d(Vec2::new(0., -312.), Vec2::new(0., 312.), Color::FUCHSIA);
+    }
+    if mirror.flips().1 {
+        gizmos.line_2d(Vec2::new(-312., 0.), Vec2::new(312., 0.), Color::FUCHSIA);
+    }
     if ui_selector.show_line {
+        let (size, (row, col)) = if precise {
+            (24., ui_selector.map_editor_cell)
+        } else {
+            (48., ui_selector.map_editor_cursor)
+        };
         let (x_start, x_end, y_start, y_end, start_edge, end_edge) = (
-            ui_selector.map_editor_cursor.1 as f32 * 48. - 312.,
-            ui_selector.map_editor_cursor.1 as f32 * 48. - 264.,
-            -(ui_selector.map_editor_cursor.0 as f32 * 48.) + 312.,
-            -(ui_selector.map_editor_cursor.0 as f32 * 48.) + 264.,
+            col as f32 * size - 312.,
+            (col + 1) as f32 * size - 312.,
+            -(row as f32 * size) + 312.,
+            -((row + 1) as f32 * size) + 312.,
             -312.,
             312.,
         );
@@ -227,9 +1043,61 @@ pub fn update_ui_editor_brush(
             Vec2::new(end_edge, y_end),
             Color::RED,
         );
-        if mouse_event.pressed(MouseButton::Left) {
+        if mouse_event.pressed(MouseButton::Left)
+            && ui_selector.map_editor_tool == EditorTool::Pencil
+            && mirror != EditorMirror::None
+            && !is_four(ui_selector.map_editor_block)
+        {
+            //镜像绘制时直接修改地图数据,有变化时重新生成精灵
+            let (block, cells) = brush_cells(&ui_selector);
+            let map = &mut gm_maps.maps[ui_selector.map_editor_level_index].map;
+            let before = map.clone();
+            if precise {
+                for ((r, c), _, _) in mirror_positions(mirror, ui_selector.map_editor_cell, 25) {
+                    if quad_cells(map, (r / 2, c / 2)).iter().all(|b| *b < 3) {
+                        map[r][c] = block;
+                    }
+                }
+            } else {
+                for (quad, h, v) in mirror_positions(mirror, ui_selector.map_editor_cursor, 12) {
+                    paint_quad(map, quad, block, flip_cells(cells, h, v));
+                }
+            }
+            if *map != before {
+                reload_editor_map(
+                    &mut commands,
+                    panel_entity,
+                    &gm_maps,
+                    &ui_selector,
+                    &gm_state,
+                    &settings,
+                );
+            }
+        } else if mouse_event.pressed(MouseButton::Left)
+            && ui_selector.map_editor_tool == EditorTool::Pencil
+        {
             let mut operator = vec![];
-            if GAME_AREA_BLOCK_FOUR.contains(&ui_selector.map_editor_block) {
+            if precise {
+                let (r, c) = ui_selector.map_editor_cell;
+                let map = &gm_maps.maps[ui_selector.map_editor_level_index].map;
+                //冰、树、水和四格块都是整块的,不能只改其中一格
+                if quad_cells(map, (r / 2, c / 2)).iter().any(|b| *b >= 3) {
+                    return;
+                }
+                let gm_block = map[r][c];
+                if gm_block == ui_selector.map_editor_block {
+                    return;
+                }
+                let mut block = Block::new(r, c, ui_selector.map_editor_block);
+                block.operate = if ui_selector.map_editor_block == 0 {
+                    BlockOperate::Remove
+                } else if gm_block == 0 {
+                    BlockOperate::Add
+                } else {
+                    BlockOperate::Change
+                };
+                operator.push(block);
+            } else if GAME_AREA_BLOCK_FOUR.contains(&ui_selector.map_editor_block) {
                 let row = ui_selector.map_editor_cursor.0 * 2;
                 let col = ui_selector.map_editor_cursor.1 * 2;
                 let editor_block = gm_maps.maps[ui_selector.map_editor_level_index].map[row][col];
@@ -298,7 +1166,7 @@ pub fn update_ui_editor_brush(
                 for b in operator.iter_mut() {
                     if b.operate == BlockOperate::Remove && block.row == b.row && block.col == b.col
                     {
-                        println!("删除");
+                        println!("remove block");
                         block.block = b.block;
                         gm_maps.maps[ui_selector.map_editor_level_index].map[block.row]
                             [block.col] = block.block;
@@ -307,7 +1175,7 @@ pub fn update_ui_editor_brush(
                     }
                     if BlockOperate::Change == b.operate {
                         if block.row == b.row && block.col == b.col {
-                            println!("替换");
+                            println!("change block");
                             block.block = b.block;
                             gm_maps.maps[ui_selector.map_editor_level_index].map[block.row]
                                 [block.col] = b.block;
@@ -343,3 +1211,765 @@ pub fn update_ui_editor_brush(
         }
     }
 }
+
+///点击工具按钮切换工具
+pub fn update_ui_editor_tool_button(
+    mut commands: Commands,
+    query_event: Query<(&Interaction, &EditorTool), Changed<Interaction>>,
+    query_button: Query<(Entity, &EditorTool), With<Button>>,
+    mut ui_selector: ResMut<UISelectInfo>,
+    mut last_tool: Local<Option<EditorTool>>,
+    right_panel_button: Query<(&Interaction, &RightPanelButton), Changed<Interaction>>,
+    mut query_mirror_text: Query<&mut Tr, With<EditorMirrorText>>,
+) {
+    for (interaction, tool) in query_event.iter() {
+        if *interaction == Interaction::Pressed {
+            ui_selector.map_editor_tool = *tool;
+        }
+    }
+    for (interaction, button) in right_panel_button.iter() {
+        if *interaction == Interaction::Pressed && *button == RightPanelButton::Mirror {
+            ui_selector.map_editor_mirror = ui_selector.map_editor_mirror.next();
+            for mut tr in query_mirror_text.iter_mut() {
+                tr.key = ui_selector.map_editor_mirror.key().to_string();
+            }
+        }
+    }
+    //粘贴时会切换到选择工具,所以按选择的工具变化刷新按钮
+    if *last_tool == Some(ui_selector.map_editor_tool) {
+        return;
+    }
+    *last_tool = Some(ui_selector.map_editor_tool);
+    for (entity, tool) in query_button.iter() {
+        if *tool == ui_selector.map_editor_tool {
+            commands.set_style(entity, class_node_tool_btn_select);
+        } else {
+            commands.set_style(entity, class_node_tool_btn_default);
+        }
+    }
+}
+
+///画笔以外的工具:矩形、边框和直线拖动松开后绘制,填充点击松开后绘制,橡皮按住擦除,吸管选择地图上的块
+#[allow(clippy::too_many_arguments)]
+pub fn update_ui_editor_tools(
+    mut commands: Commands,
+    mouse_event: Res<ButtonInput<MouseButton>>,
+    mut gizmos: Gizmos,
+    mut gm_maps: ResMut<GameMapCollection>,
+    mut ui_selector: ResMut<UISelectInfo>,
+    mut last_select_info: ResMut<LastSelectInfo>,
+    gm_state: Res<State<GameState>>,
+    settings: Res<GameSettings>,
+    gm_panel_entity: Query<Entity, (With<Sprite>, With<Clear>)>,
+    query_dialog: Query<(), With<EditorConfirm>>,
+    mut query_node_block: Query<(Entity, &mut NodeBlock, Has<BorderColor>)>,
+    mut drag_start: Local<Option<(usize, usize)>>,
+) {
+    let tool = ui_selector.map_editor_tool;
+    if !query_dialog.is_empty() || matches!(tool, EditorTool::Pencil | EditorTool::Select) {
+        *drag_start = None;
+        return;
+    }
+    let index = ui_selector.map_editor_level_index;
+    let cursor = ui_selector.map_editor_cursor;
+    if mouse_event.just_pressed(MouseButton::Left) && ui_selector.show_line {
+        *drag_start = Some(cursor);
+    }
+    let Some(start) = *drag_start else {
+        //未按下时预览将要影响的位置
+        if ui_selector.show_line {
+            match tool {
+                EditorTool::Fill => {
+                    let quads = fill_quads(&gm_maps.maps[index].map, cursor);
+                    draw_quads(&mut gizmos, &quads, Color::YELLOW);
+                }
+                _ => draw_quads(&mut gizmos, &[cursor], Color::YELLOW),
+            }
+        }
+        return;
+    };
+    let (block, cells) = brush_cells(&ui_selector);
+    let quads = match tool {
+        EditorTool::Fill => fill_quads(&gm_maps.maps[index].map, start),
+        EditorTool::Eraser | EditorTool::Eyedropper => vec![cursor],
+        //四格块只能有一个,拖动的工具只放到松开的位置
+        _ if is_four(block) => vec![cursor],
+        _ => tool_quads(tool, start, cursor),
+    };
+    let mirror = if is_four(block) || tool == EditorTool::Eyedropper {
+        EditorMirror::None
+    } else {
+        ui_selector.map_editor_mirror
+    };
+    let targets: Vec<((usize, usize), [usize; 4])> = quads
+        .iter()
+        .flat_map(|quad| mirror_positions(mirror, *quad, 12))
+        .map(|(quad, h, v)| (quad, flip_cells(cells, h, v)))
+        .collect();
+    let preview: Vec<(usize, usize)> = targets.iter().map(|(quad, _)| *quad).collect();
+    draw_quads(&mut gizmos, &preview, Color::YELLOW);
+    let mut changed = false;
+    if tool == EditorTool::Eraser && ui_selector.show_line {
+        let map = &mut gm_maps.maps[index].map;
+        let before = map.clone();
+        for (quad, cells) in targets.iter() {
+            paint_quad(map, *quad, block, *cells);
+        }
+        changed = *map != before;
+    }
+    if mouse_event.just_released(MouseButton::Left) {
+        *drag_start = None;
+        match tool {
+            EditorTool::Eyedropper => {
+                pick_block(
+                    &mut commands,
+                    &gm_maps.maps[index].map,
+                    cursor,
+                    &mut ui_selector,
+                    &mut last_select_info,
+                    &mut query_node_block,
+                );
+            }
+            EditorTool::Eraser => {}
+            _ => {
+                let map = &mut gm_maps.maps[index].map;
+                let before = map.clone();
+                for (quad, cells) in targets {
+                    paint_quad(map, quad, block, cells);
+                }
+                changed = *map != before;
+            }
+        }
+    } else if !mouse_event.pressed(MouseButton::Left) {
+        *drag_start = None;
+    }
+    if changed {
+        for entity in gm_panel_entity.iter() {
+            reload_editor_map(
+                &mut commands,
+                entity,
+                &gm_maps,
+                &ui_selector,
+                &gm_state,
+                &settings,
+            );
+        }
+    }
+}
+
+///镜像模式下同时绘制的位置,max为最大的行列号,返回位置和是否左右、上下翻转
+fn mirror_positions(
+    mirror: EditorMirror,
+    (row, col): (usize, usize),
+    max: usize,
+) -> Vec<((usize, usize), bool, bool)> {
+    let (h, v) = mirror.flips();
+    let mut positions = vec![((row, col), false, false)];
+    for (flip_h, flip_v) in [(true, false), (false, true), (true, true)] {
+        if (flip_h && !h) || (flip_v && !v) {
+            continue;
+        }
+        let position = (
+            if flip_v { max - row } else { row },
+            if flip_h { max - col } else { col },
+        );
+        //在镜像轴上时不重复绘制
+        if positions.iter().all(|(p, _, _)| *p != position) {
+            positions.push((position, flip_h, flip_v));
+        }
+    }
+    positions
+}
+
+///翻转大方块中四个格子的位置,保证砖块的形状也是对称的
+fn flip_cells(cells: [usize; 4], h: bool, v: bool) -> [usize; 4] {
+    let mut cells = cells;
+    if h {
+        cells = [cells[1], cells[0], cells[3], cells[2]];
+    }
+    if v {
+        cells = [cells[2], cells[3], cells[0], cells[1]];
+    }
+    cells
+}
+
+///工具绘制时使用的块和大方块中四个格子的值
+fn brush_cells(ui_selector: &UISelectInfo) -> (usize, [usize; 4]) {
+    let block = if ui_selector.map_editor_tool == EditorTool::Eraser {
+        0
+    } else {
+        ui_selector.map_editor_block
+    };
+    if [1, 2].contains(&block) {
+        (block, ui_selector.map_editor_blocks_inner[block - 1])
+    } else {
+        (block, [block; 4])
+    }
+}
+
+///大方块(row, col)中的四个格子
+fn quad_cells(map: &[Vec<usize>], (row, col): (usize, usize)) -> [usize; 4] {
+    [0, 1, 2, 3].map(|i| map[row * 2 + i / 2][col * 2 + i % 2])
+}
+
+///绘制一个大方块,已有四格块的位置不会被覆盖,四格块只有一个,绘制时从原位置移走
+fn paint_quad(map: &mut [Vec<usize>], quad: (usize, usize), block: usize, cells: [usize; 4]) {
+    if quad_cells(map, quad).iter().any(|b| is_four(*b)) {
+        return;
+    }
+    if is_four(block) {
+        for b in map.iter_mut().flatten() {
+            if *b == block {
+                *b = 0;
+            }
+        }
+    }
+    for (i, b) in cells.into_iter().enumerate() {
+        map[quad.0 * 2 + i / 2][quad.1 * 2 + i % 2] = b;
+    }
+}
+
+///矩形、边框和直线工具从start拖动到end经过的大方块
+fn tool_quads(tool: EditorTool, start: (usize, usize), end: (usize, usize)) -> Vec<(usize, usize)> {
+    let (r0, r1) = (start.0.min(end.0), start.0.max(end.0));
+    let (c0, c1) = (start.1.min(end.1), start.1.max(end.1));
+    match tool {
+        EditorTool::Rect => (r0..=r1)
+            .flat_map(|r| (c0..=c1).map(move |c| (r, c)))
+            .collect(),
+        EditorTool::RectOutline => (r0..=r1)
+            .flat_map(|r| (c0..=c1).map(move |c| (r, c)))
+            .filter(|(r, c)| *r == r0 || *r == r1 || *c == c0 || *c == c1)
+            .collect(),
+        EditorTool::Line => {
+            //Bresenham直线
+            let (mut r, mut c) = (start.0 as isize, start.1 as isize);
+            let (er, ec) = (end.0 as isize, end.1 as isize);
+            let (dr, dc) = ((er - r).abs(), -(ec - c).abs());
+            let (sr, sc) = ((er - r).signum(), (ec - c).signum());
+            let mut err = dr + dc;
+            let mut quads = vec![(r as usize, c as usize)];
+            while (r, c) != (er, ec) {
+                let e2 = 2 * err;
+                if e2 >= dc {
+                    err += dc;
+                    r += sr;
+                }
+                if e2 <= dr {
+                    err += dr;
+                    c += sc;
+                }
+                quads.push((r as usize, c as usize));
+            }
+            quads
+        }
+        _ => vec![end],
+    }
+}
+
+///与start四个格子完全相同且相连的大方块,含有四格块时不填充
+fn fill_quads(map: &[Vec<usize>], start: (usize, usize)) -> Vec<(usize, usize)> {
+    let target = quad_cells(map, start);
+    if target.iter().any(|b| is_four(*b)) {
+        return vec![];
+    }
+    let mut visited = [[false; 13]; 13];
+    let mut stack = vec![start];
+    let mut quads = vec![];
+    visited[start.0][start.1] = true;
+    while let Some((r, c)) = stack.pop() {
+        quads.push((r, c));
+        for (dr, dc) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+            let (nr, nc) = (r as isize + dr, c as isize + dc);
+            if !(0..13).contains(&nr) || !(0..13).contains(&nc) {
+                continue;
+            }
+            let (nr, nc) = (nr as usize, nc as usize);
+            if !visited[nr][nc] && quad_cells(map, (nr, nc)) == target {
+                visited[nr][nc] = true;
+                stack.push((nr, nc));
+            }
+        }
+    }
+    quads
+}
+
+///可达性分析:F6或右侧按钮切换,标出坦克能到和到不了的格子、各出生点到老家的路线和长度
+#[allow(clippy::too_many_arguments)]
+pub fn update_ui_editor_analysis(
+    mut commands: Commands,
+    mut gizmos: Gizmos,
+    key_event: Res<ButtonInput<KeyCode>>,
+    right_panel_button: Query<(&Interaction, &RightPanelButton), Changed<Interaction>>,
+    query_button: Query<(Entity, &RightPanelButton), With<Button>>,
+    mut query_text: Query<&mut Text, With<EditorAnalysisText>>,
+    query_dialog: Query<(), With<EditorConfirm>>,
+    gm_map: Res<GameMapCollection>,
+    mut ui_selector: ResMut<UISelectInfo>,
+    locale: Res<Locale>,
+    mut analysis: Local<Option<(usize, MapAnalysis)>>,
+) {
+    let pressed = right_panel_button.iter().any(|(interaction, button)| {
+        *interaction == Interaction::Pressed && *button == RightPanelButton::Analysis
+    });
+    if pressed || (key_event.just_pressed(KeyCode::F6) && query_dialog.is_empty()) {
+        ui_selector.map_editor_analysis = !ui_selector.map_editor_analysis;
+        for (entity, button) in query_button.iter() {
+            if *button != RightPanelButton::Analysis {
+                continue;
+            }
+            if ui_selector.map_editor_analysis {
+                commands.set_style(entity, class_node_tool_btn_select);
+            } else {
+                commands.set_style(entity, class_node_tool_btn_default);
+            }
+        }
+    }
+    let index = ui_selector.map_editor_level_index;
+    if !ui_selector.map_editor_analysis {
+        *analysis = None;
+        for mut text in query_text.iter_mut() {
+            if !text.sections[0].value.is_empty() || !text.sections[2].value.is_empty() {
+                text.sections[0].value.clear();
+                text.sections[2].value.clear();
+            }
+        }
+        return;
+    }
+    //地图变化时重新分析
+    if !gm_map.is_changed()
+        && !locale.is_changed()
+        && analysis.as_ref().is_some_and(|(i, _)| *i == index)
+    {
+        if let Some((_, result)) = analysis.as_ref() {
+            draw_analysis(&mut gizmos, &gm_map.maps[index], result);
+        }
+        return;
+    }
+    let result = analyze_map(&gm_map.maps[index]);
+    draw_analysis(&mut gizmos, &gm_map.maps[index], &result);
+    let mut enemy = 0;
+    let mut paths = vec![locale.get("editor.analysis.path")];
+    for spawn in result.spawn_paths.iter() {
+        let label = if spawn.block >= 9 {
+            enemy += 1;
+            format!("E{}", enemy)
+        } else {
+            format!("{}P", spawn.block - 6)
+        };
+        let length = spawn
+            .path
+            .as_ref()
+            .map_or("--".to_string(), |path| (path.len() - 1).to_string());
+        paths.push(format!("{} {}", label, length));
+    }
+    let mut warnings = vec![];
+    if result.home_enclosed {
+        warnings.push(locale.get("editor.analysis.home_enclosed"));
+    } else if !result.home_reachable() {
+        warnings.push(locale.get("editor.analysis.home_unreachable"));
+    }
+    if result
+        .spawn_paths
+        .iter()
+        .any(|spawn| spawn.block < 9 && spawn.path.is_none())
+    {
+        warnings.push(locale.get("editor.analysis.player_blocked"));
+    }
+    for mut text in query_text.iter_mut() {
+        text.sections[0].value = paths.join("  ");
+        text.sections[2].value = warnings.join("  ");
+    }
+    *analysis = Some((index, result));
+}
+
+///能到达的空地标为绿色,到不了的标为红色,路线敌人为黄色、玩家为青色
+fn draw_analysis(gizmos: &mut Gizmos, game_map: &GameMap, analysis: &MapAnalysis) {
+    for (row, cells) in game_map.map.iter().enumerate() {
+        for (col, block) in cells.iter().enumerate() {
+            if ![0, 3, 4].contains(block) {
+                continue;
+            }
+            let color = if analysis.reachable[row][col] {
+                Color::rgba(0.2, 0.9, 0.3, 0.5)
+            } else {
+                Color::rgba(0.9, 0.2, 0.2, 0.8)
+            };
+            gizmos.rect_2d(
+                Vec2::new(col as f32 * 24. - 300., 300. - row as f32 * 24.),
+                0.,
+                Vec2::splat(18.),
+                color,
+            );
+        }
+    }
+    for spawn in analysis.spawn_paths.iter() {
+        let Some(path) = &spawn.path else {
+            continue;
+        };
+        let color = if spawn.block >= 9 {
+            Color::YELLOW
+        } else {
+            Color::CYAN
+        };
+        gizmos.linestrip_2d(
+            path.iter()
+                .map(|(row, col)| Vec2::new(*col as f32 * 24. - 288., 288. - *row as f32 * 24.)),
+            color,
+        );
+    }
+}
+
+///用gizmo画出大方块的边框作为预览
+fn draw_quads(gizmos: &mut Gizmos, quads: &[(usize, usize)], color: Color) {
+    for (row, col) in quads.iter() {
+        gizmos.rect_2d(
+            Vec2::new(*col as f32 * 48. - 288., 288. - *row as f32 * 48.),
+            0.,
+            Vec2::splat(48.),
+            color,
+        );
+    }
+}
+
+///吸管:选择大方块中的块,砖块和铁块同时取得格子的样式,并更新左侧块面板
+fn pick_block(
+    commands: &mut Commands,
+    map: &[Vec<usize>],
+    quad: (usize, usize),
+    ui_selector: &mut UISelectInfo,
+    last_select_info: &mut LastSelectInfo,
+    query_node_block: &mut Query<(Entity, &mut NodeBlock, Has<BorderColor>)>,
+) {
+    let cells = quad_cells(map, quad);
+    let block = cells
+        .iter()
+        .copied()
+        .find(|b| is_four(*b))
+        .or_else(|| cells.iter().copied().find(|b| *b != 0))
+        .unwrap_or(0);
+    let inner = cells.map(|b| if b == block { b } else { 0 });
+    select_palette_block(
+        commands,
+        block,
+        inner,
+        ui_selector,
+        last_select_info,
+        query_node_block,
+    );
+}
+
+///选择块面板中的块,砖块和铁块同时设置格子的样式并刷新面板
+fn select_palette_block(
+    commands: &mut Commands,
+    block: usize,
+    inner: [usize; 4],
+    ui_selector: &mut UISelectInfo,
+    last_select_info: &mut LastSelectInfo,
+    query_node_block: &mut Query<(Entity, &mut NodeBlock, Has<BorderColor>)>,
+) {
+    ui_selector.map_editor_block = block;
+    if [1, 2].contains(&block) {
+        ui_selector.map_editor_blocks_inner[block - 1] = inner;
+    }
+    for (entity, mut node_block, container) in query_node_block.iter_mut() {
+        if node_block.type_index != block {
+            continue;
+        }
+        if container {
+            if let Some(last_entity) = last_select_info.last_map_editor_block {
+                commands.set_style(last_entity, class_wd_node_block_container_default);
+            }
+            commands.set_style(entity, class_wd_node_block_container_select);
+            last_select_info.last_map_editor_block = Some(entity);
+        } else if [1, 2].contains(&block) {
+            node_block.current = ui_selector.map_editor_blocks_inner[block - 1][node_block.index];
+            commands.set_style(entity, class_wd_node_block_item);
+        }
+    }
+}
+
+///点击图案库中的图案,设置砖块或铁块四个格子的样式,选择其它块时切换到砖块
+pub fn update_ui_editor_pattern(
+    mut commands: Commands,
+    query_event: Query<(&Interaction, &EditorPattern), Changed<Interaction>>,
+    mut ui_selector: ResMut<UISelectInfo>,
+    mut last_select_info: ResMut<LastSelectInfo>,
+    mut query_node_block: Query<(Entity, &mut NodeBlock, Has<BorderColor>)>,
+) {
+    for (interaction, pattern) in query_event.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let block = if [1, 2].contains(&ui_selector.map_editor_block) {
+            ui_selector.map_editor_block
+        } else {
+            1
+        };
+        select_palette_block(
+            &mut commands,
+            block,
+            GAME_EDITOR_PATTERNS[pattern.0].map(|b| b * block),
+            &mut ui_selector,
+            &mut last_select_info,
+            &mut query_node_block,
+        );
+    }
+}
+
+///选择工具拖动框选区域,Ctrl+C复制、Ctrl+X剪切、Ctrl+V粘贴、Ctrl+S把选区存入图章库,
+///[和]切换图章库中的图章。粘贴时预览跟随光标,H水平翻转、V垂直翻转、R旋转90°,点击放置,Esc或右键退出
+#[allow(clippy::too_many_arguments)]
+pub fn update_ui_editor_clipboard(
+    mut commands: Commands,
+    key_event: Res<ButtonInput<KeyCode>>,
+    mouse_event: Res<ButtonInput<MouseButton>>,
+    mut gizmos: Gizmos,
+    mut gm_maps: ResMut<GameMapCollection>,
+    mut ui_selector: ResMut<UISelectInfo>,
+    mut clipboard: ResMut<EditorClipboard>,
+    mut library: ResMut<EditorStampLibrary>,
+    mut history: ResMut<EditorHistory>,
+    locale: Res<Locale>,
+    (gm_state, settings): (Res<State<GameState>>, Res<GameSettings>),
+    gm_panel_entity: Query<Entity, (With<Sprite>, With<Clear>)>,
+    query_dialog: Query<(), With<EditorConfirm>>,
+    query_toast: Query<Entity, With<EditorToast>>,
+) {
+    if !query_dialog.is_empty() || ui_selector.map_editor_renaming {
+        clipboard.drag_start = None;
+        return;
+    }
+    let index = ui_selector.map_editor_level_index;
+    let cursor = ui_selector.map_editor_cursor;
+    let ctrl = key_event.pressed(KeyCode::ControlLeft) || key_event.pressed(KeyCode::ControlRight);
+    //切换地图后选区失效,剪贴板保留用于粘贴到其它地图
+    if clipboard
+        .selection
+        .is_some_and(|selection| selection.map_index != index)
+    {
+        clipboard.selection = None;
+    }
+    let mut changed = false;
+    if ctrl {
+        let selection = clipboard
+            .selection
+            .map(|selection| (selection.start, selection.end));
+        if key_event.just_pressed(KeyCode::KeyC) || key_event.just_pressed(KeyCode::KeyX) {
+            if let Some((start, end)) = selection {
+                let map = &mut gm_maps.maps[index].map;
+                clipboard.stamp = Some(EditorStamp::from_map(map, start, end));
+                if key_event.just_pressed(KeyCode::KeyX) {
+                    let before = map.clone();
+                    for quad in tool_quads(EditorTool::Rect, start, end) {
+                        paint_quad(map, quad, 0, [0; 4]);
+                    }
+                    if let Some(action) = EditorAction::diff(&before, map) {
+                        history.push(index, action);
+                        changed = true;
+                    }
+                }
+            }
+        } else if key_event.just_pressed(KeyCode::KeyV) && clipboard.stamp.is_some() {
+            clipboard.pasting = true;
+        } else if key_event.just_pressed(KeyCode::KeyS) {
+            if let Some((start, end)) = selection {
+                let mut stamp = EditorStamp::from_map(&gm_maps.maps[index].map, start, end);
+                stamp.name = format!("stamp{}", library.stamps.len() + 1);
+                library.stamps.push(stamp.clone());
+                library.index = library.stamps.len() - 1;
+                let message = format!("{}: {}", locale.get("editor.stamp_saved"), stamp.name);
+                match save_stamps(&library) {
+                    Ok(()) => spawn_toast(&mut commands, query_toast.iter(), message, false),
+                    Err(err) => {
+                        let message = format!("{}: {}", locale.get("editor.save_failed"), err);
+                        spawn_toast(&mut commands, query_toast.iter(), message, true);
+                    }
+                }
+            }
+        }
+    } else if key_event.just_pressed(KeyCode::BracketLeft)
+        || key_event.just_pressed(KeyCode::BracketRight)
+    {
+        let len = library.stamps.len();
+        if len == 0 {
+            spawn_toast(
+                &mut commands,
+                query_toast.iter(),
+                locale.get("editor.stamp_empty"),
+                true,
+            );
+        } else {
+            //第一次切换时从当前图章开始
+            if clipboard.pasting {
+                library.index = if key_event.just_pressed(KeyCode::BracketLeft) {
+                    (library.index + len - 1) % len
+                } else {
+                    (library.index + 1) % len
+                };
+            }
+            library.index = library.index.min(len - 1);
+            let stamp = library.stamps[library.index].clone();
+            spawn_toast(&mut commands, query_toast.iter(), stamp.name.clone(), false);
+            clipboard.stamp = Some(stamp);
+            clipboard.pasting = true;
+        }
+    }
+    if clipboard.pasting {
+        ui_selector.map_editor_tool = EditorTool::Select;
+        clipboard.selection = None;
+        clipboard.drag_start = None;
+        if key_event.just_pressed(KeyCode::Escape) || mouse_event.just_pressed(MouseButton::Right) {
+            clipboard.pasting = false;
+        }
+    }
+    if ui_selector.map_editor_tool != EditorTool::Select {
+        clipboard.drag_start = None;
+    } else if let (true, Some(stamp)) = (clipboard.pasting, clipboard.stamp.as_mut()) {
+        if !ctrl {
+            if key_event.just_pressed(KeyCode::KeyH) {
+                stamp.mirror_horizontal();
+            } else if key_event.just_pressed(KeyCode::KeyV) {
+                stamp.mirror_vertical();
+            } else if key_event.just_pressed(KeyCode::KeyR) {
+                stamp.rotate();
+            }
+        }
+        if ui_selector.show_line {
+            let quads: Vec<(usize, usize)> = stamp
+                .quads()
+                .map(|((r, c), _)| (cursor.0 + r, cursor.1 + c))
+                .filter(|(r, c)| *r < 13 && *c < 13)
+                .collect();
+            draw_quads(&mut gizmos, &quads, Color::GREEN);
+            if mouse_event.just_released(MouseButton::Left) {
+                let map = &mut gm_maps.maps[index].map;
+                let before = map.clone();
+                for ((r, c), cells) in stamp.quads() {
+                    if cursor.0 + r < 13 && cursor.1 + c < 13 {
+                        paint_quad(map, (cursor.0 + r, cursor.1 + c), 0, cells);
+                    }
+                }
+                changed |= *map != before;
+            }
+        }
+    } else {
+        if key_event.just_pressed(KeyCode::Escape) {
+            clipboard.selection = None;
+        }
+        if mouse_event.just_pressed(MouseButton::Left) && ui_selector.show_line {
+            clipboard.drag_start = Some(cursor);
+        }
+        if let Some(start) = clipboard.drag_start {
+            clipboard.selection = Some(Selection {
+                map_index: index,
+                start,
+                end: cursor,
+            });
+            if !mouse_event.pressed(MouseButton::Left) {
+                clipboard.drag_start = None;
+            }
+        }
+        if let Some(Selection { start, end, .. }) = clipboard.selection {
+            let (r0, c0) = (start.0.min(end.0), start.1.min(end.1));
+            let (r1, c1) = (start.0.max(end.0), start.1.max(end.1));
+            let size = Vec2::new((c1 - c0 + 1) as f32, (r1 - r0 + 1) as f32) * 48.;
+            let corner = Vec2::new(c0 as f32 * 48. - 312., 312. - r0 as f32 * 48.);
+            gizmos.rect_2d(corner + size * Vec2::new(0.5, -0.5), 0., size, Color::CYAN);
+        }
+    }
+    if changed {
+        for entity in gm_panel_entity.iter() {
+            reload_editor_map(
+                &mut commands,
+                entity,
+                &gm_maps,
+                &ui_selector,
+                &gm_state,
+                &settings,
+            );
+        }
+    }
+}
+
+///点击试玩或按F5时复制当前地图开始游戏,原地图不会被修改
+#[allow(clippy::too_many_arguments)]
+pub fn update_ui_editor_test_play(
+    mut commands: Commands,
+    key_event: Res<ButtonInput<KeyCode>>,
+    right_panel_button: Query<(&Interaction, &RightPanelButton), Changed<Interaction>>,
+    gm_map: Res<GameMapCollection>,
+    ui_selector: Res<UISelectInfo>,
+    mut test_play: ResMut<EditorTestPlay>,
+    mut record: ResMut<GameRecord>,
+    settings: Res<GameSettings>,
+    locale: Res<Locale>,
+    mut next_state: ResMut<NextState<GameState>>,
+    query_dialog: Query<(), With<EditorConfirm>>,
+    query_toast: Query<Entity, With<EditorToast>>,
+) {
+    let pressed = right_panel_button.iter().any(|(interaction, button)| {
+        *interaction == Interaction::Pressed && *button == RightPanelButton::TestPlay
+    });
+    let hotkey = key_event.just_pressed(KeyCode::F5)
+        && !ui_selector.map_editor_renaming
+        && query_dialog.is_empty();
+    if !pressed && !hotkey {
+        return;
+    }
+    let snapshot = gm_map.maps[ui_selector.map_editor_level_index].clone();
+    if let Err(err) = snapshot.validate() {
+        let message = format!("{}: {}", locale.get("editor.test_play_failed"), err);
+        spawn_toast(&mut commands, query_toast.iter(), message, true);
+        return;
+    }
+    //试玩的地图只保存在EditorTestPlay中,游戏从那里读取,地图列表不变
+    test_play.map = Some(snapshot);
+    *record = GameRecord {
+        difficulty: settings.difficulty,
+        ..default()
+    };
+    next_state.set(GameState::UIGameEnter);
+}
+
+///试玩中按F5结束试玩
+pub fn update_editor_test_play_end(
+    key_event: Res<ButtonInput<KeyCode>>,
+    test_play: Res<EditorTestPlay>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if test_play.active() && key_event.just_pressed(KeyCode::F5) {
+        next_state.set(GameState::UIMapEditor);
+    }
+}
+
+///进入编辑器时切换到用户地图包,还没有用户地图包时复制当前的地图;从试玩回来时不切换
+pub fn setup_editor_user_pack(
+    mut gm_map: ResMut<GameMapCollection>,
+    mut packs: ResMut<MapPacks>,
+    mut ui_selector: ResMut<UISelectInfo>,
+    test_play: Res<EditorTestPlay>,
+) {
+    if test_play.active() {
+        return;
+    }
+    let index = packs.user_index().unwrap_or_else(|| {
+        packs.packs.push(new_user_map_pack(gm_map.maps.clone()));
+        packs.packs.len() - 1
+    });
+    //已经在使用用户地图包时保留没有保存的修改
+    if packs.current == index {
+        return;
+    }
+    packs.current = index;
+    gm_map.clone_from(&packs.packs[index].maps);
+    //编辑器只编辑用户地图包,撤销记录和选择的地图仍然对应
+    let last = gm_map.maps.len() - 1;
+    ui_selector.map_editor_level_index = ui_selector.map_editor_level_index.min(last);
+    ui_selector.map_index = 0;
+}
+
+///回到编辑器时丢弃试玩的地图快照
+pub fn exit_editor_test_play(mut test_play: ResMut<EditorTestPlay>) {
+    test_play.map = None;
+}
@@ -4,18 +4,21 @@ use bevy::prelude::*;
 
 use crate::{
     res::{
-        Bullet, Clear, Colider, Enemy, GameDirection, GameMapCollection, GameState, Moving, Player,
-        UISelectInfo,
+        Bullet, Clear, Colider, EditorTestPlay, Enemy, GameDirection, GameEvent, GameMapCollection,
+        GameOverText, GameRecord, GameSettings, GameState, HomeWall, Moving, Player, PowerUp,
+        UISelectInfo, GAME_ENEMY_MAX_ALIVE, GAME_OVER_DELAY, GAME_PLAYER_RESPAWN_DELAY,
+        GAME_PLAYER_SPAWN_PROTECT,
     },
     utils::{
+        input::{apply_directions, GameInput},
         util::{point_direction, random_move, transform_to_pos},
         widget::{sprite, sprite_root},
     },
 };
 
 use super::{
-    class::game_class::{class_sprite_bullet, class_sprite_panel},
-    widget::wd_load_game_map,
+    class::game_class::{class_sprite_bullet, class_sprite_game_over, class_sprite_panel},
+    widget::{wd_load_game_map, wd_sprite_enemy, wd_sprite_player},
 };
 
 pub fn setup_ui_game(
@@ -23,42 +26,26 @@ pub fn setup_ui_game(
     gm_map: Res<GameMapCollection>,
     ui_map_select: Res<UISelectInfo>,
     gm_state: Res<State<GameState>>,
+    settings: Res<GameSettings>,
+    test_play: Res<EditorTestPlay>,
 ) {
+    let game_map = if *gm_state.get() == GameState::UIMapEditor {
+        &gm_map.maps[ui_map_select.map_editor_level_index]
+    } else {
+        test_play.game_map(&gm_map, ui_map_select.map_index)
+    };
     sprite_root(class_sprite_panel, commands, Clear, |gc| {
-        wd_load_game_map(gc, &gm_map, &ui_map_select, &gm_state);
+        wd_load_game_map(gc, game_map, &ui_map_select, &gm_state, &settings);
     });
 }
 
-pub fn update_ui_game(mut query_player: Query<&mut Player>, key_event: Res<ButtonInput<KeyCode>>) {
+///按玩家绑定的设备读取方向和开火,键盘和手柄共用方向栈
+pub fn update_ui_game(mut query_player: Query<&mut Player>, input: GameInput) {
     for mut player in query_player.iter_mut() {
         if let Some(keys) = player.keys_binding {
-            if key_event.just_pressed(keys.up) {
-                player.direction_stack.push(GameDirection::Up);
-            } else if key_event.just_released(keys.up) {
-                player.direction_stack.retain(|&x| x != GameDirection::Up);
-            }
-            if key_event.just_pressed(keys.down) {
-                player.direction_stack.push(GameDirection::Down);
-            } else if key_event.just_released(keys.down) {
-                player.direction_stack.retain(|&x| x != GameDirection::Down);
-            }
-            if key_event.just_pressed(keys.left) {
-                player.direction_stack.push(GameDirection::Left);
-            } else if key_event.just_released(keys.left) {
-                player.direction_stack.retain(|&x| x != GameDirection::Left);
-            }
-            if key_event.just_pressed(keys.right) {
-                player.direction_stack.push(GameDirection::Right);
-            } else if key_event.just_released(keys.right) {
-                player
-                    .direction_stack
-                    .retain(|&x| x != GameDirection::Right);
-            }
-            if key_event.just_pressed(keys.fire) {
-                player.fire = true;
-            } else if key_event.just_released(keys.fire) {
-                player.fire = false;
-            }
+            let directions = input.directions(player.device, &keys);
+            apply_directions(&mut player.direction_stack, directions);
+            player.fire = input.fire(player.device, &keys);
         }
     }
 }
@@ -161,11 +148,18 @@ pub fn update_ui_enemy(
     mut query_enemy: Query<(&mut Player, &Transform, &mut Enemy)>,
     gm_map: Res<GameMapCollection>,
     select_info: Res<UISelectInfo>,
+    record: Res<GameRecord>,
+    test_play: Res<EditorTestPlay>,
 ) {
     for (mut player, transform, mut enemy) in query_enemy.iter_mut() {
+        //定时道具生效时敌人停止行动
+        if record.is_active(PowerUp::Clock) {
+            player.direction_stack.clear();
+            continue;
+        }
         if enemy.random_path.is_empty() {
             enemy.random_path = random_move(
-                &gm_map.maps[select_info.map_index].map,
+                &test_play.game_map(&gm_map, select_info.map_index).map,
                 transform_to_pos(transform),
                 10,
             );
@@ -192,6 +186,158 @@ pub fn update_ui_enemy(
     }
 }
 
+///场上敌人不足时,按间隔在空闲的出生点补充敌人
+#[allow(clippy::too_many_arguments)]
+pub fn update_enemy_spawn(
+    mut commands: Commands,
+    gm_map: Res<GameMapCollection>,
+    select_info: Res<UISelectInfo>,
+    record: Res<GameRecord>,
+    time: Res<Time>,
+    query_enemy: Query<(), With<Enemy>>,
+    query_tank: Query<&Transform, With<Player>>,
+    panel: Query<Entity, (With<Clear>, With<Sprite>)>,
+    test_play: Res<EditorTestPlay>,
+    mut elapsed: Local<Duration>,
+) {
+    let map = test_play.game_map(&gm_map, select_info.map_index);
+    let alive = query_enemy.iter().count();
+    if alive >= GAME_ENEMY_MAX_ALIVE || alive >= record.enemy_remain {
+        *elapsed = Duration::ZERO;
+        return;
+    }
+    *elapsed += time.delta();
+    if *elapsed < record.difficulty.enemy_spawn_interval() {
+        return;
+    }
+    let spawn_block = map
+        .to_blocks()
+        .into_iter()
+        .filter(|block| [9, 10, 11].contains(&block.block))
+        .find(|block| {
+            let (x, y) = block.to_pos();
+            query_tank.iter().all(|transform| {
+                (transform.translation.x - x).abs() >= 48.
+                    || (transform.translation.y - y).abs() >= 48.
+            })
+        });
+    if let Some(mut block) = spawn_block {
+        *elapsed = Duration::ZERO;
+        //地图设置了敌人数量时按顺序出场,否则由出生点决定
+        let spawned = map.enemy_total() - record.enemy_remain + alive;
+        if let Some(enemy) = map.enemy_type(spawned) {
+            block.block = enemy;
+        }
+        for panel_entity in panel.iter() {
+            commands.entity(panel_entity).with_children(|gc| {
+                wd_sprite_enemy(gc, &block);
+            });
+        }
+    }
+}
+
+///玩家被击毁后,还有生命时在出生点重生并获得短暂保护
+#[allow(clippy::too_many_arguments)]
+pub fn update_player_respawn(
+    mut commands: Commands,
+    gm_map: Res<GameMapCollection>,
+    select_info: Res<UISelectInfo>,
+    settings: Res<GameSettings>,
+    mut record: ResMut<GameRecord>,
+    time: Res<Time>,
+    query_player: Query<&Player>,
+    panel: Query<Entity, (With<Clear>, With<Sprite>)>,
+    test_play: Res<EditorTestPlay>,
+    mut elapsed: Local<[Duration; 2]>,
+) {
+    for slot in 0..select_info.game_mode.players() {
+        if record.lives[slot] == 0
+            || query_player
+                .iter()
+                .any(|player| player.player_slot() == Some(slot))
+        {
+            elapsed[slot] = Duration::ZERO;
+            continue;
+        }
+        elapsed[slot] += time.delta();
+        if elapsed[slot] < GAME_PLAYER_RESPAWN_DELAY {
+            continue;
+        }
+        elapsed[slot] = Duration::ZERO;
+        if let Some(block) = test_play
+            .game_map(&gm_map, select_info.map_index)
+            .to_blocks()
+            .into_iter()
+            .find(|block| block.block == 7 + slot)
+        {
+            for panel_entity in panel.iter() {
+                commands.entity(panel_entity).with_children(|gc| {
+                    wd_sprite_player(gc, &block, &settings);
+                });
+            }
+            record.player_power_up[slot] = Some((
+                PowerUp::Protect,
+                Timer::new(GAME_PLAYER_SPAWN_PROTECT, TimerMode::Once),
+            ));
+        }
+    }
+}
+
+///老家被击毁、所有玩家都没有生命或时间用完时游戏结束,提示后返回菜单,试玩时返回编辑器
+#[allow(clippy::too_many_arguments)]
+pub fn update_game_over(
+    mut commands: Commands,
+    mut events: EventReader<GameEvent>,
+    record: Res<GameRecord>,
+    select_info: Res<UISelectInfo>,
+    time: Res<Time>,
+    query_player: Query<&Player>,
+    mut query_game_over: Query<&mut GameOverText>,
+    panel: Query<Entity, (With<Clear>, With<Sprite>)>,
+    mut next_state: ResMut<NextState<GameState>>,
+    test_play: Res<EditorTestPlay>,
+) {
+    let home_destroyed = events
+        .read()
+        .any(|event| matches!(event, GameEvent::HomeDestroyed));
+    if let Ok(mut game_over) = query_game_over.get_single_mut() {
+        if game_over.0.tick(time.delta()).just_finished() {
+            next_state.set(test_play.exit_state());
+        }
+        return;
+    }
+    let players_out = (0..select_info.game_mode.players()).all(|slot| {
+        record.lives[slot] == 0
+            && query_player
+                .iter()
+                .all(|player| player.player_slot() != Some(slot))
+    });
+    let time_out = record.time_limit.is_some_and(|time| time.is_zero());
+    if home_destroyed || players_out || time_out {
+        for panel_entity in panel.iter() {
+            commands.entity(panel_entity).with_children(|gc| {
+                sprite(
+                    class_sprite_game_over,
+                    gc,
+                    GameOverText(Timer::new(GAME_OVER_DELAY, TimerMode::Once)),
+                );
+            });
+        }
+    }
+}
+
+///新生成的玩家继承记录中的等级
+pub fn update_player_record(
+    mut query_player: Query<&mut Player, Added<Player>>,
+    record: Res<GameRecord>,
+) {
+    for mut player in query_player.iter_mut() {
+        if let Some(slot) = player.player_slot() {
+            player.level = record.level[slot];
+        }
+    }
+}
+
 //todo 简化代码 转向矫正
 pub fn update_check_collision(
     mut query_movable: Query<(&mut Transform, &Colider, &mut Moving), With<Moving>>,
@@ -327,6 +473,9 @@ pub fn update_bullet_boom(
     mut commands: Commands,
     mut query: Query<(Entity, &mut Transform, &mut Bullet, &Moving), With<Bullet>>,
     query_colider: Query<(Entity, &Transform, &Colider), Without<Bullet>>,
+    query_wall: Query<(), With<HomeWall>>,
+    record: Res<GameRecord>,
+    mut game_events: EventWriter<GameEvent>,
 ) {
     let mut iter = query.iter_mut().collect::<Vec<_>>();
     for i in 0..iter.len() {
@@ -369,9 +518,30 @@ pub fn update_bullet_boom(
                         commands.entity(iter[i].0).despawn_recursive();
                     }
                     if colider.is_home() {
-                        //game_over
+                        game_events.send(GameEvent::HomeDestroyed);
                     } else {
-                        commands.entity(entity).despawn_recursive();
+                        match colider.index {
+                            //保护中的玩家不会被击毁
+                            7 | 8 if record.is_protected(colider.index - 7) => {}
+                            7 | 8 => {
+                                game_events.send(GameEvent::PlayerDestroyed {
+                                    player: colider.index - 7,
+                                });
+                                commands.entity(entity).despawn_recursive();
+                            }
+                            9..=11 => {
+                                game_events.send(GameEvent::EnemyDestroyed {
+                                    player: iter[i].2.player_slot(),
+                                    enemy: colider.index,
+                                });
+                                commands.entity(entity).despawn_recursive();
+                            }
+                            //道具加固的围墙不会被击毁
+                            _ if query_wall.contains(entity) => {}
+                            _ => {
+                                commands.entity(entity).despawn_recursive();
+                            }
+                        }
                     }
                 }
             }
@@ -0,0 +1,195 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    res::{
+        Clear, EditorTestPlay, GameEvent, GameMapCollection, GameRecord, GameSource, HudEnemyIcon,
+        HudPowerUp, HudText, PowerUp, Tr, UISelectInfo, GAME_ENEMY_SCORE, GAME_ENEMY_TOTAL,
+        GAME_ICON_FLAG,
+    },
+    utils::widget::{atlas_image, grid, image, node_children, node_root, text},
+};
+
+use super::class::hud_class::{
+    class_node_hud_enemy_grid, class_node_hud_enemy_icon, class_node_hud_icon_text,
+    class_node_hud_item, class_node_hud_panel, class_node_hud_power_up, class_node_hud_text,
+};
+
+///进入关卡时重置本关的记录,生命、分数和等级会带到下一关
+pub fn setup_game_record(
+    mut record: ResMut<GameRecord>,
+    select_info: Res<UISelectInfo>,
+    gm_map: Res<GameMapCollection>,
+    test_play: Res<EditorTestPlay>,
+) {
+    let map = test_play.game_map(&gm_map, select_info.map_index);
+    record.stage = select_info.map_index;
+    record.enemy_remain = map.enemy_total();
+    record.power_up = None;
+    record.time_limit = (map.time_limit > 0).then(|| Duration::from_secs(map.time_limit as u64));
+}
+
+pub fn setup_ui_hud(
+    commands: Commands,
+    select_info: Res<UISelectInfo>,
+    gm_map: Res<GameMapCollection>,
+    test_play: Res<EditorTestPlay>,
+) {
+    let map = test_play.game_map(&gm_map, select_info.map_index);
+    let time_limit = map.time_limit > 0;
+    node_root(class_node_hud_panel, commands, Clear, |gc| {
+        grid(
+            GAME_ENEMY_TOTAL / 2,
+            2,
+            24.,
+            class_node_hud_enemy_grid,
+            (),
+            gc,
+            (),
+            |gc, r, c| {
+                atlas_image(class_node_hud_enemy_icon, gc, HudEnemyIcon(r * 2 + c));
+            },
+        );
+        for (index, key) in ["hud.player1", "hud.player2"]
+            .into_iter()
+            .take(select_info.game_mode.players())
+            .enumerate()
+        {
+            node_children(class_node_hud_item, gc, (), |gc| {
+                text(
+                    [key, " ", ""],
+                    class_node_hud_text,
+                    gc,
+                    (HudText::Lives(index), Tr::new(key)),
+                );
+            });
+            node_children(class_node_hud_item, gc, (), |gc| {
+                text(["", ""], class_node_hud_text, gc, HudText::Score(index));
+            });
+        }
+        node_children(class_node_hud_item, gc, (), |gc| {
+            text(
+                [GAME_ICON_FLAG, " ", ""],
+                (class_node_hud_text, class_node_hud_icon_text),
+                gc,
+                HudText::Stage,
+            );
+        });
+        node_children(class_node_hud_item, gc, (), |gc| {
+            image(class_node_hud_power_up, gc, HudPowerUp);
+            text([""], class_node_hud_text, gc, HudText::PowerUp);
+        });
+        if time_limit {
+            node_children(class_node_hud_item, gc, (), |gc| {
+                text(
+                    ["hud.time", " ", ""],
+                    class_node_hud_text,
+                    gc,
+                    (HudText::Time, Tr::new("hud.time")),
+                );
+            });
+        }
+    });
+}
+
+///根据游戏事件更新记录
+pub fn update_game_record(
+    mut events: EventReader<GameEvent>,
+    mut record: ResMut<GameRecord>,
+    time: Res<Time>,
+) {
+    for event in events.read() {
+        match *event {
+            GameEvent::EnemyDestroyed { player, enemy } => {
+                record.enemy_remain = record.enemy_remain.saturating_sub(1);
+                if let Some(player) = player {
+                    record.score[player] += GAME_ENEMY_SCORE[enemy - 9];
+                }
+            }
+            GameEvent::PlayerDestroyed { player } => {
+                record.lives[player] = record.lives[player].saturating_sub(1);
+                record.level[player] = 1;
+                record.player_power_up[player] = None;
+            }
+            GameEvent::PowerUp { player, power_up } => {
+                record.score[player] += 500;
+                if power_up == PowerUp::Tank {
+                    record.lives[player] += 1;
+                }
+                if let Some(duration) = power_up.duration() {
+                    let effect = Some((power_up, Timer::new(duration, TimerMode::Once)));
+                    if power_up.is_shared() {
+                        record.power_up = effect;
+                    } else {
+                        record.player_power_up[player] = effect;
+                    }
+                }
+            }
+            GameEvent::HomeDestroyed => {}
+        }
+    }
+    if let Some(time_limit) = record.time_limit {
+        record.time_limit = Some(time_limit.saturating_sub(time.delta()));
+    }
+    if record.active_power_up().is_some() {
+        let delta = time.delta();
+        let record = record.as_mut();
+        for effect in record
+            .player_power_up
+            .iter_mut()
+            .chain([&mut record.power_up])
+        {
+            if let Some((_, timer)) = effect {
+                if timer.tick(delta).finished() {
+                    *effect = None;
+                }
+            }
+        }
+    }
+}
+
+///记录变化时刷新HUD
+pub fn update_ui_hud(
+    record: Res<GameRecord>,
+    gm_res: Res<GameSource>,
+    mut query_text: Query<(&mut Text, &HudText)>,
+    mut query_icon: Query<(&mut Visibility, &HudEnemyIcon), Without<HudPowerUp>>,
+    mut query_power_up: Query<(&mut Visibility, &mut UiImage), With<HudPowerUp>>,
+) {
+    for (mut text, hud_text) in query_text.iter_mut() {
+        let value = match *hud_text {
+            HudText::Stage => (record.stage + 1).to_string(),
+            HudText::Lives(index) => record.lives[index].to_string(),
+            HudText::Score(index) => format!("{:06}", record.score[index]),
+            HudText::PowerUp => record
+                .active_power_up()
+                .map_or(String::new(), |(_, timer)| {
+                    format!("{}s", timer.remaining_secs().ceil())
+                }),
+            HudText::Time => record.time_limit.map_or(String::new(), |time| {
+                format!("{}", time.as_secs_f32().ceil())
+            }),
+        };
+        if let Some(section) = text.sections.last_mut() {
+            if section.value != value {
+                section.value = value;
+            }
+        }
+    }
+    for (mut visibility, icon) in query_icon.iter_mut() {
+        *visibility = if icon.0 < record.enemy_remain {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+    for (mut visibility, mut image) in query_power_up.iter_mut() {
+        if let Some((power_up, _)) = record.active_power_up() {
+            image.texture = gm_res.foods[power_up.to_index()].clone();
+            *visibility = Visibility::Inherited;
+        } else {
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use bevy::prelude::*;
+
+use crate::{
+    res::{
+        Clear, GameSettings, GameState, InputDevice, KeysSettingItem, KeysSettingTip, Locale,
+        MenuInput, UISelectInfo, KEYS_ACTION_LIST,
+    },
+    utils::{
+        class::StyleCommand,
+        util::{key_name, save_settings},
+        widget::{grid, node_children, node_root, node_text, node_text_t},
+    },
+};
+
+use super::class::{
+    keys_class::{
+        class_node_keys_grid, class_node_keys_item, class_node_keys_text, class_node_keys_tip,
+        class_node_keys_title,
+    },
+    menu_class::{
+        class_node_game_panel, class_node_menu_text_default, class_node_menu_text_hover,
+        class_node_root,
+    },
+};
+
+///按键设置中输入设备所在的行
+const KEYS_DEVICE_ROW: usize = KEYS_ACTION_LIST.len();
+///按键设置的行数,最后一行为返回按钮
+const KEYS_SETTING_ROWS: usize = KEYS_ACTION_LIST.len() + 2;
+
+pub fn setup_ui_keys(commands: Commands, mut select_info: ResMut<UISelectInfo>) {
+    select_info.keys_setting = (0, 0);
+    select_info.keys_listening = false;
+    node_root(class_node_root, commands, Clear, |gc| {
+        node_children(class_node_game_panel, gc, (), |gc| {
+            node_text_t("keys.title", class_node_keys_title, gc, ());
+            grid(
+                KEYS_SETTING_ROWS + 1,
+                3,
+                45.,
+                class_node_keys_grid,
+                class_node_keys_item,
+                gc,
+                (),
+                |gc, r, c| match (r, c) {
+                    (0, 0) => {}
+                    (0, _) => {
+                        node_text(["IP", "IIP"][c - 1], class_node_keys_text, gc, ());
+                    }
+                    (r, 0) if r <= KEYS_ACTION_LIST.len() => {
+                        node_text_t(KEYS_ACTION_LIST[r - 1], class_node_keys_text, gc, ());
+                    }
+                    (r, 0) if r == KEYS_DEVICE_ROW + 1 => {
+                        node_text_t("keys.device", class_node_keys_text, gc, ());
+                    }
+                    (r, c) if r <= KEYS_DEVICE_ROW + 1 => {
+                        node_text(
+                            "",
+                            class_node_keys_text,
+                            gc,
+                            (Interaction::None, KeysSettingItem(r - 1, c - 1)),
+                        );
+                    }
+                    (r, 1) => {
+                        node_text_t(
+                            "common.back",
+                            class_node_keys_text,
+                            gc,
+                            (Interaction::None, KeysSettingItem(r - 1, 0)),
+                        );
+                    }
+                    _ => {}
+                },
+            );
+            node_text("", class_node_keys_tip, gc, KeysSettingTip);
+        });
+    });
+}
+
+///离开按键设置时写入磁盘
+pub fn exit_ui_keys(settings: Res<GameSettings>) {
+    save_settings(&settings);
+}
+
+///WASD选择,Enter开始监听或切换设备,按下新按键完成绑定,Esc取消或返回菜单
+#[allow(clippy::too_many_arguments)]
+pub fn update_ui_keys(
+    mut commands: Commands,
+    key_event: Res<ButtonInput<KeyCode>>,
+    menu_input: Res<MenuInput>,
+    locale: Res<Locale>,
+    query_hover: Query<(&Interaction, &KeysSettingItem), Changed<Interaction>>,
+    mut query_item: Query<(&Parent, &mut Text, &KeysSettingItem), Without<KeysSettingTip>>,
+    mut query_tip: Query<&mut Text, With<KeysSettingTip>>,
+    mut settings: ResMut<GameSettings>,
+    mut select_info: ResMut<UISelectInfo>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let mut tip = None;
+    let (mut row, mut slot) = select_info.keys_setting;
+    if select_info.keys_listening {
+        if let Some(&key) = key_event.get_just_pressed().next() {
+            select_info.keys_listening = false;
+            if key != KeyCode::Escape {
+                let bindings = [settings.keys_binding.0, settings.keys_binding.1];
+                let in_use = bindings.iter().enumerate().any(|(index, binding)| {
+                    binding
+                        .keys()
+                        .iter()
+                        .enumerate()
+                        .any(|(action, k)| *k == key && (action, index) != (row, slot))
+                });
+                if in_use {
+                    tip = Some(format!("{} {}", key_name(key), locale.get("keys.in_use")));
+                } else if slot == 0 {
+                    settings.keys_binding.0.set_key(row, key);
+                } else {
+                    settings.keys_binding.1.set_key(row, key);
+                }
+            }
+        }
+    } else {
+        let mut confirm = false;
+        if menu_input.up {
+            row = (row + KEYS_SETTING_ROWS - 1) % KEYS_SETTING_ROWS;
+        } else if menu_input.down {
+            row = (row + 1) % KEYS_SETTING_ROWS;
+        } else if menu_input.left || menu_input.right {
+            slot = 1 - slot;
+        } else if menu_input.confirm {
+            confirm = true;
+        } else if menu_input.back {
+            next_state.set(GameState::UIMenu);
+        }
+        for (interaction, item) in query_hover.iter() {
+            match *interaction {
+                Interaction::Pressed => {
+                    (row, slot) = (item.0, item.1);
+                    confirm = true;
+                }
+                Interaction::Hovered => {
+                    (row, slot) = (item.0, item.1);
+                }
+                _ => {}
+            }
+        }
+        if row == KEYS_SETTING_ROWS - 1 {
+            slot = 0;
+            if confirm {
+                next_state.set(GameState::UIMenu);
+            }
+        } else if row == KEYS_DEVICE_ROW {
+            if confirm && slot == 0 {
+                settings.devices.0 = settings.devices.0.next();
+            } else if confirm {
+                settings.devices.1 = settings.devices.1.next();
+            }
+        } else if confirm {
+            select_info.keys_listening = true;
+            tip = Some(locale.get("keys.listening"));
+        }
+    }
+    if (row, slot) != select_info.keys_setting {
+        select_info.keys_setting = (row, slot);
+        tip = Some(String::new());
+    }
+    if let Some(tip) = tip {
+        for mut text in query_tip.iter_mut() {
+            text.sections[0].value = tip.clone();
+        }
+    }
+    for (parent, mut text, item) in query_item.iter_mut() {
+        let selected = (item.0, item.1) == select_info.keys_setting;
+        if item.0 == KEYS_DEVICE_ROW {
+            let value = if item.1 == 0 {
+                device_name(settings.devices.0, &locale)
+            } else {
+                device_name(settings.devices.1, &locale)
+            };
+            if text.sections[0].value != value {
+                text.sections[0].value = value;
+            }
+        } else if item.0 < KEYS_ACTION_LIST.len() {
+            let value = if selected && select_info.keys_listening {
+                String::from("...")
+            } else if item.1 == 0 {
+                key_name(settings.keys_binding.0.keys()[item.0])
+            } else {
+                key_name(settings.keys_binding.1.keys()[item.0])
+            };
+            if text.sections[0].value != value {
+                text.sections[0].value = value;
+            }
+        }
+        if selected {
+            commands.set_style(parent.get(), class_node_menu_text_hover);
+        } else {
+            commands.set_style(parent.get(), class_node_menu_text_default);
+        }
+    }
+}
+
+fn device_name(device: InputDevice, locale: &Locale) -> String {
+    match device {
+        InputDevice::Keyboard => locale.get("keys.keyboard"),
+        InputDevice::Gamepad(id) => format!("{} {}", locale.get("keys.gamepad"), id + 1),
+    }
+}
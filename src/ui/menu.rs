@@ -1,10 +1,10 @@
 use bevy::prelude::*;
 
 use crate::{
-    res::{Clear, GameState, UISelectInfo, MENU_LIST},
+    res::{Clear, GameMode, GameState, MenuInput, UISelectInfo, MENU_LIST},
     utils::{
         class::StyleCommand,
-        widget::{atlas_image, grid, image, node_children, node_root, node_text, GridItemInfo},
+        widget::{atlas_image, grid, image, node_children, node_root, node_text_t, GridItemInfo},
     },
 };
 
@@ -19,7 +19,7 @@ pub fn setup_ui_menu(commands: Commands) {
         node_children(class_node_game_panel, gc, (), |gc| {
             image(class_node_game_logo_image, gc, ());
             grid(
-                3,
+                MENU_LIST.len(),
                 2,
                 60.,
                 class_node_menu,
@@ -28,7 +28,7 @@ pub fn setup_ui_menu(commands: Commands) {
                 (),
                 |gc, r, c| match c {
                     1 => {
-                        node_text(MENU_LIST[r], class_node_menu_text, gc, Interaction::None);
+                        node_text_t(MENU_LIST[r], class_node_menu_text, gc, Interaction::None);
                     }
                     _ => {
                         atlas_image(class_node_tank_selector, gc, GridItemInfo(r, c));
@@ -43,30 +43,24 @@ pub fn update_ui_menu(
     mut commands: Commands,
     query_hover: Query<&Interaction>,
     query_entity: Query<&Parent, With<Interaction>>,
-    key_event: Res<ButtonInput<KeyCode>>,
+    menu_input: Res<MenuInput>,
     mut query_visible: Query<&mut Visibility, With<TextureAtlas>>,
     mut ui_select_info: ResMut<UISelectInfo>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
-    if key_event.just_pressed(KeyCode::KeyW) || key_event.just_pressed(KeyCode::ArrowUp) {
-        ui_select_info.menu = (ui_select_info.menu + 2) % 3;
-    } else if key_event.just_pressed(KeyCode::KeyS) || key_event.just_pressed(KeyCode::ArrowDown) {
-        ui_select_info.menu = (ui_select_info.menu + 1) % 3;
-    } else if key_event.just_pressed(KeyCode::Enter) {
-        if ui_select_info.menu == 2 {
-            next_state.set(GameState::UIMapEditor);
-        } else {
-            next_state.set(GameState::UIGameEnter);
-        }
+    let len = MENU_LIST.len();
+    let mut confirm = false;
+    if menu_input.up {
+        ui_select_info.menu = (ui_select_info.menu + len - 1) % len;
+    } else if menu_input.down {
+        ui_select_info.menu = (ui_select_info.menu + 1) % len;
+    } else if menu_input.confirm {
+        confirm = true;
     }
     for (index, interaction) in query_hover.iter().enumerate() {
         match *interaction {
             Interaction::Pressed => {
-                if ui_select_info.menu == 2 {
-                    next_state.set(GameState::UIMapEditor);
-                } else {
-                    next_state.set(GameState::UIGameEnter);
-                }
+                confirm = true;
             }
             Interaction::Hovered => {
                 ui_select_info.menu = index;
@@ -74,6 +68,27 @@ pub fn update_ui_menu(
             _ => {}
         }
     }
+    if confirm {
+        match ui_select_info.menu {
+            0 => {
+                ui_select_info.game_mode = GameMode::OnePlayer;
+                next_state.set(GameState::UIMapPack);
+            }
+            1 => {
+                ui_select_info.game_mode = GameMode::TwoPlayers;
+                next_state.set(GameState::UIMapPack);
+            }
+            2 => {
+                next_state.set(GameState::UIMapEditor);
+            }
+            3 => {
+                next_state.set(GameState::UIKeysSetting);
+            }
+            _ => {
+                next_state.set(GameState::UISettings);
+            }
+        }
+    }
     for (index, parent) in query_entity.iter().enumerate() {
         if index == ui_select_info.menu {
             commands.set_style(parent.get(), class_node_menu_text_hover);
@@ -2,4 +2,11 @@ pub mod widget;
 pub mod menu;
 pub mod class;
 pub mod game;
-pub mod editor;
\ No newline at end of file
+pub mod editor;
+pub mod hud;
+pub mod pause;
+pub mod power_up;
+pub mod stage;
+pub mod keys;
+pub mod settings;
+pub mod pack;
\ No newline at end of file
@@ -0,0 +1,126 @@
+use bevy::prelude::*;
+
+use crate::{
+    res::{
+        Clear, GameMapCollection, GameState, Locale, MapPackItem, MapPackSource, MapPacks,
+        MenuInput, UISelectInfo,
+    },
+    utils::{
+        class::StyleCommand,
+        widget::{node_children, node_root, node_text_t, text},
+    },
+};
+
+use super::class::{
+    keys_class::class_node_keys_title,
+    menu_class::{
+        class_node_game_panel, class_node_menu_text_default, class_node_menu_text_hover,
+        class_node_root,
+    },
+    pack_class::{class_node_pack_item, class_node_pack_list, class_node_pack_text},
+};
+
+///开始游戏前选择地图包,列出名称、版本、作者和地图数量
+pub fn setup_ui_map_pack(
+    commands: Commands,
+    packs: Res<MapPacks>,
+    locale: Res<Locale>,
+    mut select_info: ResMut<UISelectInfo>,
+) {
+    select_info.map_pack = packs.current;
+    node_root(class_node_root, commands, Clear, |gc| {
+        node_children(class_node_game_panel, gc, (), |gc| {
+            node_text_t("pack.title", class_node_keys_title, gc, ());
+            node_children(class_node_pack_list, gc, (), |gc| {
+                for (index, pack) in packs.packs.iter().enumerate() {
+                    let mut info = Vec::new();
+                    if !pack.maps.pack_version.is_empty() {
+                        info.push(format!("v{}", pack.maps.pack_version));
+                    }
+                    if !pack.maps.author.is_empty() {
+                        info.push(pack.maps.author.clone());
+                    }
+                    if pack.source == MapPackSource::User {
+                        info.push(locale.get("pack.user_dir"));
+                    }
+                    info.push(format!(
+                        "{} {}",
+                        pack.maps.maps.len(),
+                        locale.get("pack.maps")
+                    ));
+                    let item = (Interaction::None, MapPackItem(index));
+                    node_children(class_node_pack_item, gc, item, |gc| {
+                        let sections = [pack.title(&locale), format!("\n{}", info.join("  "))];
+                        text(sections, class_node_pack_text, gc, ());
+                    });
+                }
+                let back = (Interaction::None, MapPackItem(packs.packs.len()));
+                node_children(class_node_pack_item, gc, back, |gc| {
+                    node_text_t("common.back", class_node_pack_text, gc, ());
+                });
+            });
+        });
+    });
+}
+
+///上下选择,确认后切换地图包并进入关卡选择
+#[allow(clippy::too_many_arguments)]
+pub fn update_ui_map_pack(
+    mut commands: Commands,
+    menu_input: Res<MenuInput>,
+    query_hover: Query<(&Interaction, &MapPackItem), Changed<Interaction>>,
+    query_item: Query<(Entity, &MapPackItem)>,
+    mut packs: ResMut<MapPacks>,
+    mut gm_map: ResMut<GameMapCollection>,
+    mut select_info: ResMut<UISelectInfo>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let len = packs.packs.len() + 1;
+    let mut confirm = false;
+    if menu_input.up {
+        select_info.map_pack = (select_info.map_pack + len - 1) % len;
+    } else if menu_input.down {
+        select_info.map_pack = (select_info.map_pack + 1) % len;
+    } else if menu_input.confirm {
+        confirm = true;
+    } else if menu_input.back {
+        next_state.set(GameState::UIMenu);
+    }
+    for (interaction, item) in query_hover.iter() {
+        match *interaction {
+            Interaction::Pressed => {
+                select_info.map_pack = item.0;
+                confirm = true;
+            }
+            Interaction::Hovered => {
+                select_info.map_pack = item.0;
+            }
+            _ => {}
+        }
+    }
+    if confirm {
+        let index = select_info.map_pack;
+        if index == packs.packs.len() {
+            next_state.set(GameState::UIMenu);
+        } else {
+            if index != packs.current {
+                //编辑器中没有保存的修改留在用户地图包中,再次进入编辑器时还在
+                let current = packs.current;
+                if packs.user_index() == Some(current) {
+                    packs.packs[current].maps.clone_from(&gm_map);
+                }
+                packs.current = index;
+                gm_map.clone_from(&packs.packs[index].maps);
+                select_info.map_index = 0;
+            }
+            next_state.set(GameState::UIStage);
+        }
+    }
+    for (entity, item) in query_item.iter() {
+        if item.0 == select_info.map_pack {
+            commands.set_style(entity, class_node_menu_text_hover);
+        } else {
+            commands.set_style(entity, class_node_menu_text_default);
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use bevy::prelude::*;
+
+use crate::{
+    res::{
+        EditorTestPlay, GameState, MenuInput, PauseMenu, PauseMenuItem, PauseState, Player,
+        UISelectInfo, PAUSE_MENU_LIST,
+    },
+    utils::{
+        class::StyleCommand,
+        widget::{grid, node_root, node_text_t},
+    },
+};
+
+use super::class::{
+    menu_class::{class_node_menu_text, class_node_menu_text_default, class_node_menu_text_hover},
+    pause_class::{class_node_pause_mask, class_node_pause_menu_item, class_node_pause_panel},
+};
+
+///游戏中按Esc或手柄Start切换暂停
+pub fn update_ui_game_pause(
+    menu_input: Res<MenuInput>,
+    pause_state: Res<State<PauseState>>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+) {
+    if menu_input.back {
+        next_pause_state.set(match pause_state.get() {
+            PauseState::Running => PauseState::Paused,
+            PauseState::Paused => PauseState::Running,
+        });
+    }
+}
+
+///暂停时冻结时间(Animator和计时器都依赖时间),并清空玩家的按键状态
+pub fn setup_ui_pause(
+    commands: Commands,
+    mut time: ResMut<Time<Virtual>>,
+    mut query_player: Query<&mut Player>,
+    mut ui_select_info: ResMut<UISelectInfo>,
+) {
+    time.pause();
+    for mut player in query_player.iter_mut() {
+        player.direction_stack.clear();
+        player.fire = false;
+    }
+    ui_select_info.pause_menu = 0;
+    node_root(class_node_pause_mask, commands, PauseMenu, |gc| {
+        grid(
+            PAUSE_MENU_LIST.len(),
+            1,
+            50.,
+            class_node_pause_panel,
+            class_node_pause_menu_item,
+            gc,
+            (),
+            |gc, r, _| {
+                node_text_t(
+                    PAUSE_MENU_LIST[r],
+                    class_node_menu_text,
+                    gc,
+                    (Interaction::None, PauseMenuItem(r)),
+                );
+            },
+        );
+    });
+}
+
+pub fn exit_ui_pause(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+///重新开始时离开UIGameEnter再进入,重新执行进入游戏的流程
+pub fn setup_ui_game_restart(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::UIGameEnter);
+}
+
+///离开游戏时恢复暂停状态
+pub fn exit_ui_game_pause(mut next_pause_state: ResMut<NextState<PauseState>>) {
+    next_pause_state.set(PauseState::Running);
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_ui_pause(
+    mut commands: Commands,
+    query_hover: Query<(&Interaction, &PauseMenuItem), Changed<Interaction>>,
+    query_entity: Query<(&Parent, &PauseMenuItem)>,
+    menu_input: Res<MenuInput>,
+    mut ui_select_info: ResMut<UISelectInfo>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+    test_play: Res<EditorTestPlay>,
+) {
+    let len = PAUSE_MENU_LIST.len();
+    let mut confirm = false;
+    if menu_input.up {
+        ui_select_info.pause_menu = (ui_select_info.pause_menu + len - 1) % len;
+    } else if menu_input.down {
+        ui_select_info.pause_menu = (ui_select_info.pause_menu + 1) % len;
+    } else if menu_input.confirm {
+        confirm = true;
+    }
+    for (interaction, item) in query_hover.iter() {
+        match *interaction {
+            Interaction::Pressed => {
+                ui_select_info.pause_menu = item.0;
+                confirm = true;
+            }
+            Interaction::Hovered => {
+                ui_select_info.pause_menu = item.0;
+            }
+            _ => {}
+        }
+    }
+    if confirm {
+        next_pause_state.set(PauseState::Running);
+        match ui_select_info.pause_menu {
+            1 => {
+                next_state.set(GameState::UIGameRestart);
+            }
+            2 => {
+                next_state.set(test_play.exit_state());
+            }
+            _ => {}
+        }
+    }
+    for (parent, item) in query_entity.iter() {
+        if item.0 == ui_select_info.pause_menu {
+            commands.set_style(parent.get(), class_node_menu_text_hover);
+        } else {
+            commands.set_style(parent.get(), class_node_menu_text_default);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use bevy::prelude::*;
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    res::{
+        Block, Clear, Colider, EditorTestPlay, Enemy, Food, GameEvent, GameMapCollection,
+        GameRecord, HomeWall, Player, PowerUp, UISelectInfo, GAME_PLAYER_MAX_LEVEL,
+    },
+    utils::widget::sprite,
+};
+
+use super::class::game_class::{class_sprite_block, class_sprite_food};
+
+///玩家击毁敌人时按地图设置的概率在随机位置掉落道具,地图上同时只有一个道具
+pub fn update_power_up_drop(
+    mut commands: Commands,
+    mut events: EventReader<GameEvent>,
+    gm_map: Res<GameMapCollection>,
+    select_info: Res<UISelectInfo>,
+    query_food: Query<Entity, With<Food>>,
+    panel: Query<Entity, (With<Clear>, With<Sprite>)>,
+    test_play: Res<EditorTestPlay>,
+) {
+    let mut rng = rand::thread_rng();
+    let drop_rate = test_play
+        .game_map(&gm_map, select_info.map_index)
+        .power_up_drop_rate;
+    for event in events.read() {
+        if !matches!(
+            event,
+            GameEvent::EnemyDestroyed {
+                player: Some(_),
+                ..
+            }
+        ) || !rng.gen_ratio(drop_rate.min(100), 100)
+        {
+            continue;
+        }
+        for entity in query_food.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        let power_up = *PowerUp::ALL.choose(&mut rng).unwrap();
+        let block = Block::new(rng.gen_range(0..13) * 2, rng.gen_range(0..13) * 2, 0);
+        for panel_entity in panel.iter() {
+            commands.entity(panel_entity).with_children(|gc| {
+                sprite(class_sprite_food, gc, (Food(power_up), block));
+            });
+        }
+    }
+}
+
+///玩家坦克碰到道具时拾取
+pub fn update_power_up_pick(
+    mut commands: Commands,
+    query_food: Query<(Entity, &Transform, &Food)>,
+    query_player: Query<(&Transform, &Player)>,
+    mut game_events: EventWriter<GameEvent>,
+) {
+    for (entity, food_transform, food) in query_food.iter() {
+        for (transform, player) in query_player.iter() {
+            if let Some(slot) = player.player_slot() {
+                if (transform.translation.x - food_transform.translation.x).abs() < 40.
+                    && (transform.translation.y - food_transform.translation.y).abs() < 40.
+                {
+                    game_events.send(GameEvent::PowerUp {
+                        player: slot,
+                        power_up: food.0,
+                    });
+                    commands.entity(entity).despawn_recursive();
+                    break;
+                }
+            }
+        }
+    }
+}
+
+///立即生效的道具:炸弹消灭场上所有敌人,星星和枪提升拾取玩家的等级
+pub fn update_power_up_effect(
+    mut commands: Commands,
+    mut events: EventReader<GameEvent>,
+    mut record: ResMut<GameRecord>,
+    mut query_player: Query<&mut Player, Without<Enemy>>,
+    query_enemy: Query<Entity, With<Enemy>>,
+) {
+    for event in events.read() {
+        if let GameEvent::PowerUp { player, power_up } = *event {
+            match power_up {
+                PowerUp::Boom => {
+                    for entity in query_enemy.iter() {
+                        commands.entity(entity).despawn_recursive();
+                        record.enemy_remain = record.enemy_remain.saturating_sub(1);
+                    }
+                }
+                PowerUp::Star | PowerUp::Gun => {
+                    for mut tank in query_player.iter_mut() {
+                        if tank.player_slot() == Some(player) {
+                            tank.level = if power_up == PowerUp::Gun {
+                                GAME_PLAYER_MAX_LEVEL
+                            } else {
+                                (tank.level + 1).min(GAME_PLAYER_MAX_LEVEL)
+                            };
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+///铁锹道具生效时老家周围的砖墙换成不会被击毁的铁墙,失效后恢复为砖墙;
+///只替换地图上原本是空地、砖块或铁块的格子
+#[allow(clippy::too_many_arguments)]
+pub fn update_power_up_iron(
+    mut commands: Commands,
+    record: Res<GameRecord>,
+    gm_map: Res<GameMapCollection>,
+    select_info: Res<UISelectInfo>,
+    test_play: Res<EditorTestPlay>,
+    query_block: Query<(Entity, &Block), With<Colider>>,
+    query_wall: Query<(), With<HomeWall>>,
+    panel: Query<Entity, (With<Clear>, With<Sprite>)>,
+) {
+    let active = record.is_active(PowerUp::Iron);
+    if active != query_wall.is_empty() {
+        return;
+    }
+    let game_map = test_play.game_map(&gm_map, select_info.map_index);
+    let Some(home) = game_map
+        .to_blocks()
+        .into_iter()
+        .find(|block| block.block == 6)
+    else {
+        return;
+    };
+    let is_home = |r: usize, c: usize| {
+        (home.row..home.row + 2).contains(&r) && (home.col..home.col + 2).contains(&c)
+    };
+    let cells: Vec<(usize, usize)> = (home.row.saturating_sub(1)..(home.row + 3).min(26))
+        .flat_map(|r| (home.col.saturating_sub(1)..(home.col + 3).min(26)).map(move |c| (r, c)))
+        .filter(|(r, c)| !is_home(*r, *c) && game_map.map[*r][*c] <= 2)
+        .collect();
+    //原来的砖块或铁墙都在这些格子上,一起移除;坦克的块编号大于2,道具没有碰撞
+    for (entity, block) in query_block.iter() {
+        if block.block <= 2 && cells.contains(&(block.row, block.col)) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    let block = if active { 2 } else { 1 };
+    for panel_entity in panel.iter() {
+        commands.entity(panel_entity).with_children(|gc| {
+            for (r, c) in cells.iter() {
+                let bundle = (Block::new(*r, *c, block), Colider::new(block, 24., 24.));
+                if active {
+                    sprite(class_sprite_block, gc, (bundle, HomeWall));
+                } else {
+                    sprite(class_sprite_block, gc, bundle);
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,230 @@
+use bevy::{
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+    window::{PrimaryWindow, WindowMode},
+};
+
+use crate::{
+    res::{
+        Clear, Difficulty, FpsText, GameSettings, GameState, Locale, MenuInput, SettingsItem,
+        UISelectInfo, GAME_LANGUAGE_LIST, GAME_RESOLUTION_LIST, GAME_VOLUME_MAX,
+        GAME_WINDOW_MODE_LIST, SETTINGS_LIST,
+    },
+    utils::{
+        class::StyleCommand,
+        util::save_settings,
+        widget::{grid, node_children, node_root, node_text, node_text_t},
+    },
+};
+
+use super::class::{
+    keys_class::class_node_keys_title,
+    menu_class::{
+        class_node_game_panel, class_node_menu_text_default, class_node_menu_text_hover,
+        class_node_root,
+    },
+    settings_class::{
+        class_node_fps, class_node_fps_text, class_node_settings_grid, class_node_settings_item,
+        class_node_settings_text,
+    },
+};
+
+pub fn setup_ui_settings(commands: Commands, mut select_info: ResMut<UISelectInfo>) {
+    select_info.settings_menu = 0;
+    node_root(class_node_root, commands, Clear, |gc| {
+        node_children(class_node_game_panel, gc, (), |gc| {
+            node_text_t("settings.title", class_node_keys_title, gc, ());
+            grid(
+                SETTINGS_LIST.len() + 1,
+                2,
+                45.,
+                class_node_settings_grid,
+                class_node_settings_item,
+                gc,
+                (),
+                |gc, r, c| match (r, c) {
+                    (r, 0) if r < SETTINGS_LIST.len() => {
+                        node_text_t(SETTINGS_LIST[r], class_node_settings_text, gc, ());
+                    }
+                    (r, 1) if r < SETTINGS_LIST.len() => {
+                        node_text(
+                            "",
+                            class_node_settings_text,
+                            gc,
+                            (Interaction::None, SettingsItem(r)),
+                        );
+                    }
+                    (r, 0) => {
+                        node_text_t(
+                            "common.back",
+                            class_node_settings_text,
+                            gc,
+                            (Interaction::None, SettingsItem(r)),
+                        );
+                    }
+                    _ => {}
+                },
+            );
+        });
+    });
+}
+
+///离开设置界面时写入磁盘
+pub fn exit_ui_settings(settings: Res<GameSettings>) {
+    save_settings(&settings);
+}
+
+///上下选择,左右修改,修改后立即生效
+#[allow(clippy::too_many_arguments)]
+pub fn update_ui_settings(
+    mut commands: Commands,
+    menu_input: Res<MenuInput>,
+    locale: Res<Locale>,
+    query_hover: Query<(&Interaction, &SettingsItem), Changed<Interaction>>,
+    mut query_item: Query<(&Parent, &mut Text, &SettingsItem)>,
+    mut settings: ResMut<GameSettings>,
+    mut select_info: ResMut<UISelectInfo>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let len = SETTINGS_LIST.len() + 1;
+    let mut step = 0;
+    if menu_input.up {
+        select_info.settings_menu = (select_info.settings_menu + len - 1) % len;
+    } else if menu_input.down {
+        select_info.settings_menu = (select_info.settings_menu + 1) % len;
+    } else if menu_input.left {
+        step = -1;
+    } else if menu_input.right || menu_input.confirm {
+        step = 1;
+    } else if menu_input.back {
+        next_state.set(GameState::UIMenu);
+    }
+    for (interaction, item) in query_hover.iter() {
+        match *interaction {
+            Interaction::Pressed => {
+                select_info.settings_menu = item.0;
+                step = 1;
+            }
+            Interaction::Hovered => {
+                select_info.settings_menu = item.0;
+            }
+            _ => {}
+        }
+    }
+    if step != 0 {
+        if select_info.settings_menu == SETTINGS_LIST.len() {
+            next_state.set(GameState::UIMenu);
+        } else {
+            change_settings(&mut settings, select_info.settings_menu, step);
+        }
+    }
+    for (parent, mut text, item) in query_item.iter_mut() {
+        if item.0 < SETTINGS_LIST.len() {
+            let value = format!("< {} >", settings_value(&settings, item.0, &locale));
+            if text.sections[0].value != value {
+                text.sections[0].value = value;
+            }
+        }
+        if item.0 == select_info.settings_menu {
+            commands.set_style(parent.get(), class_node_menu_text_hover);
+        } else {
+            commands.set_style(parent.get(), class_node_menu_text_default);
+        }
+    }
+}
+
+///在列表中循环切换
+fn cycle<T: PartialEq + Copy>(list: &[T], current: T, step: isize) -> T {
+    let index = list.iter().position(|v| *v == current).unwrap_or(0) as isize;
+    list[(index + step).rem_euclid(list.len() as isize) as usize]
+}
+
+fn change_settings(settings: &mut GameSettings, row: usize, step: isize) {
+    let volume = |value: usize| value.saturating_add_signed(step).min(GAME_VOLUME_MAX);
+    match row {
+        0 => settings.resolution = cycle(&GAME_RESOLUTION_LIST, settings.resolution, step),
+        1 => settings.window_mode = cycle(&GAME_WINDOW_MODE_LIST, settings.window_mode, step),
+        2 => settings.volume.master = volume(settings.volume.master),
+        3 => settings.volume.music = volume(settings.volume.music),
+        4 => settings.volume.effect = volume(settings.volume.effect),
+        5 => settings.difficulty = cycle(&Difficulty::ALL, settings.difficulty, step),
+        6 => settings.show_fps = !settings.show_fps,
+        _ => {
+            settings.language =
+                cycle(&GAME_LANGUAGE_LIST, settings.language.as_str(), step).to_string()
+        }
+    }
+}
+
+fn settings_value(settings: &GameSettings, row: usize, locale: &Locale) -> String {
+    match row {
+        0 => format!("{}x{}", settings.resolution.0, settings.resolution.1),
+        1 => locale.get(match settings.window_mode {
+            WindowMode::BorderlessFullscreen => "settings.borderless",
+            WindowMode::Fullscreen | WindowMode::SizedFullscreen => "settings.fullscreen",
+            WindowMode::Windowed => "settings.windowed",
+        }),
+        2 => settings.volume.master.to_string(),
+        3 => settings.volume.music.to_string(),
+        4 => settings.volume.effect.to_string(),
+        5 => locale.get(match settings.difficulty {
+            Difficulty::Easy => "difficulty.easy",
+            Difficulty::Normal => "difficulty.normal",
+            Difficulty::Hard => "difficulty.hard",
+        }),
+        6 => locale.get(if settings.show_fps {
+            "settings.on"
+        } else {
+            "settings.off"
+        }),
+        _ => locale.get(&format!("language.{}", settings.language)),
+    }
+}
+
+///设置变化时同步到窗口和帧率显示
+pub fn update_settings_apply(
+    settings: Res<GameSettings>,
+    mut query_window: Query<&mut Window, With<PrimaryWindow>>,
+    mut query_fps: Query<&mut Visibility, With<FpsText>>,
+) {
+    for mut window in query_window.iter_mut() {
+        if window.mode != settings.window_mode {
+            window.mode = settings.window_mode;
+        }
+        let (width, height) = settings.resolution;
+        if window.resolution.width() != width || window.resolution.height() != height {
+            window.resolution.set(width, height);
+        }
+    }
+    for mut visibility in query_fps.iter_mut() {
+        *visibility = if settings.show_fps {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+pub fn setup_fps(commands: Commands) {
+    node_root(class_node_fps, commands, (), |gc| {
+        node_text("", class_node_fps_text, gc, FpsText);
+    });
+}
+
+pub fn update_fps(
+    settings: Res<GameSettings>,
+    diagnostics: Res<DiagnosticsStore>,
+    mut query_fps: Query<&mut Text, With<FpsText>>,
+) {
+    if !settings.show_fps {
+        return;
+    }
+    if let Some(fps) = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+    {
+        for mut text in query_fps.iter_mut() {
+            text.sections[0].value = format!("FPS {:.0}", fps);
+        }
+    }
+}
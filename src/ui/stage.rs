@@ -0,0 +1,156 @@
+use bevy::prelude::*;
+
+use crate::{
+    res::{
+        Clear, EditorTestPlay, Enemy, GameMapCollection, GameProgress, GameRecord, GameSettings,
+        GameState, Locale, MapPacks, MenuInput, Player, StageCurtain, StageText, UISelectInfo,
+        GAME_STAGE_CLEAR_DELAY,
+    },
+    utils::{
+        animate::Animator,
+        util::save_progress,
+        widget::{node, node_root, node_text},
+    },
+};
+
+use super::class::{
+    menu_class::class_node_root,
+    stage_class::{
+        class_node_stage_curtain_bottom, class_node_stage_curtain_top, class_node_stage_text,
+    },
+};
+
+///从菜单开始新游戏,重置记录并允许选择关卡
+pub fn setup_new_game(
+    mut record: ResMut<GameRecord>,
+    mut select_info: ResMut<UISelectInfo>,
+    progress: Res<GameProgress>,
+    packs: Res<MapPacks>,
+    settings: Res<GameSettings>,
+) {
+    *record = GameRecord {
+        difficulty: settings.difficulty,
+        ..default()
+    };
+    select_info.stage_select = true;
+    select_info.map_index = select_info
+        .map_index
+        .min(progress.unlocked(packs.current()));
+}
+
+pub fn setup_ui_stage(commands: Commands) {
+    node_root(class_node_root, commands, Clear, |gc| {
+        node(
+            class_node_stage_curtain_top,
+            gc,
+            (StageCurtain, Animator::default()),
+        );
+        node(
+            class_node_stage_curtain_bottom,
+            gc,
+            (StageCurtain, Animator::default()),
+        );
+        node_text(
+            "",
+            class_node_stage_text,
+            gc,
+            StageText(Timer::new(GAME_STAGE_CLEAR_DELAY, TimerMode::Once)),
+        );
+    });
+}
+
+///幕布合上后显示关卡,可选择关卡时左右切换,确认后开始
+#[allow(clippy::too_many_arguments)]
+pub fn update_ui_stage(
+    key_event: Res<ButtonInput<KeyCode>>,
+    menu_input: Res<MenuInput>,
+    locale: Res<Locale>,
+    time: Res<Time>,
+    gm_map: Res<GameMapCollection>,
+    progress: Res<GameProgress>,
+    packs: Res<MapPacks>,
+    mut select_info: ResMut<UISelectInfo>,
+    query_curtain: Query<&Animator, With<StageCurtain>>,
+    mut query_text: Query<(&mut Text, &mut Visibility, &mut StageText)>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !query_curtain
+        .iter()
+        .all(|animator| animator.get_is_finish())
+    {
+        return;
+    }
+    //空的地图包没有关卡可玩,回到地图包选择
+    let Some(last_map) = gm_map.maps.len().checked_sub(1) else {
+        next_state.set(GameState::UIMapPack);
+        return;
+    };
+    let last = progress.unlocked(packs.current()).min(last_map);
+    for (mut text, mut visibility, mut stage_text) in query_text.iter_mut() {
+        *visibility = Visibility::Inherited;
+        if select_info.stage_select {
+            if menu_input.left {
+                select_info.map_index = (select_info.map_index + last) % (last + 1);
+            } else if menu_input.right {
+                select_info.map_index = (select_info.map_index + 1) % (last + 1);
+            } else if menu_input.confirm || key_event.just_pressed(KeyCode::KeyJ) {
+                next_state.set(GameState::UIGameEnter);
+            }
+            text.sections[0].value = format!(
+                "< {} {} >",
+                locale.get("stage.stage"),
+                select_info.map_index + 1
+            );
+        } else {
+            text.sections[0].value = format!(
+                "{} {}",
+                locale.get("stage.stage"),
+                select_info.map_index + 1
+            );
+            if stage_text.0.tick(time.delta()).just_finished() {
+                next_state.set(GameState::UIGameEnter);
+            }
+        }
+    }
+}
+
+///敌人全部消灭后进入下一关,最后一关之后回到第一关,试玩时回到编辑器
+#[allow(clippy::too_many_arguments)]
+pub fn update_stage_clear(
+    time: Res<Time>,
+    gm_map: Res<GameMapCollection>,
+    query_enemy: Query<(), With<Enemy>>,
+    query_player: Query<&Player>,
+    mut record: ResMut<GameRecord>,
+    mut progress: ResMut<GameProgress>,
+    packs: Res<MapPacks>,
+    mut select_info: ResMut<UISelectInfo>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut clear_timer: Local<Option<Timer>>,
+    test_play: Res<EditorTestPlay>,
+) {
+    if record.enemy_remain > 0 || !query_enemy.is_empty() {
+        *clear_timer = None;
+        return;
+    }
+    let timer =
+        clear_timer.get_or_insert_with(|| Timer::new(GAME_STAGE_CLEAR_DELAY, TimerMode::Once));
+    if timer.tick(time.delta()).finished() {
+        *clear_timer = None;
+        if test_play.active() {
+            next_state.set(GameState::UIMapEditor);
+            return;
+        }
+        for player in query_player.iter() {
+            if let Some(slot) = player.player_slot() {
+                record.level[slot] = player.level;
+            }
+        }
+        select_info.map_index = (select_info.map_index + 1) % gm_map.maps.len();
+        select_info.stage_select = false;
+        if progress.unlock(packs.current(), select_info.map_index) {
+            save_progress(&progress);
+        }
+        next_state.set(GameState::UIStage);
+    }
+}
@@ -4,14 +4,16 @@ use bevy::prelude::*;
 
 use crate::{
     res::{
-        Block, Colider, Enemy, GameDirection, GameMapCollection, GameState, LastSelectInfo, Moving, NodeBlock, Player, Relate, UISelectInfo, GAME_ICON_ARROW_LEFT
+        Block, Colider, EditorPattern, Enemy, GameDirection, GameMap, GameMapCollection, GameMode,
+        GameSettings, GameState, LastSelectInfo, MapNameText, Moving, NodeBlock, Player, Relate,
+        Tr, UISelectInfo, GAME_EDITOR_PATTERNS, GAME_ICON_ARROW_LEFT,
     },
     utils::{
         animate::{Animator, LoopStrategy},
         class::StyleCommand,
         widget::{
-            atlas_image, grid, image, node_children, sprite, sprite_children, sprite_sheet, text,
-            GridInfo,
+            atlas_image, button_children, grid, image, node, node_children, sprite,
+            sprite_children, sprite_sheet, text, GridInfo, GridItemInfo,
         },
     },
 };
@@ -19,11 +21,14 @@ use crate::{
 use super::class::{
     class_node_fill,
     editor_class::{
-        class_node_collapse_background, class_node_collapse_title, class_node_column_align_center,
-        class_node_margin_bottom, class_node_padding_left, class_node_text_title,
-        class_wd_node_block_container, class_wd_node_block_container_default,
-        class_wd_node_block_container_select, class_wd_node_block_contianer_image,
-        class_wd_node_block_item, class_wd_node_block_size, class_wd_node_block_size_inner,
+        class_node_collapse_background, class_node_collapse_item_default,
+        class_node_collapse_item_hover, class_node_collapse_title, class_node_column_align_center,
+        class_node_map_name_style, class_node_map_name_text, class_node_margin_bottom,
+        class_node_padding_left, class_node_pattern, class_node_pattern_cell,
+        class_node_pattern_cell_on, class_node_text_title, class_wd_node_block_container,
+        class_wd_node_block_container_default, class_wd_node_block_container_select,
+        class_wd_node_block_contianer_image, class_wd_node_block_item, class_wd_node_block_size,
+        class_wd_node_block_size_inner,
     },
     game_class::{class_sprite_block, class_sprite_sheet_block},
 };
@@ -48,12 +53,84 @@ pub fn wd_sprite_block(gc: &mut ChildBuilder, block: &Block) {
         }
     });
 }
+///在玩家出生点生成玩家,7为玩家1,8为玩家2
+pub fn wd_sprite_player(gc: &mut ChildBuilder, block: &Block, settings: &GameSettings) -> Entity {
+    sprite_sheet(
+        class_sprite_sheet_block,
+        gc,
+        (
+            *block,
+            Moving::new(GameDirection::Up, 2.),
+            if block.block == 7 {
+                Player::new_player1(settings.keys_binding.0, settings.devices.0)
+            } else {
+                Player::new_player2(settings.keys_binding.1, settings.devices.1)
+            },
+            Colider::new(block.block, 48., 48.),
+        ),
+    )
+}
+///在敌人出生点生成敌人
+pub fn wd_sprite_enemy(gc: &mut ChildBuilder, block: &Block) -> Entity {
+    sprite_sheet(
+        class_sprite_sheet_block,
+        gc,
+        (
+            *block,
+            Moving::new(GameDirection::Down, 2.),
+            Player::new_enemy(block.block),
+            Enemy::default(),
+            Colider::new(block.block, 48., 48.),
+        ),
+    )
+}
+///编辑器的地图列表,expanded为true时生成后直接展开
+pub fn wd_level_list(
+    gc: &mut ChildBuilder,
+    gm_maps: &GameMapCollection,
+    select_info: &UISelectInfo,
+    expanded: bool,
+) {
+    wd_setup_collapse_grid(
+        "editor.level",
+        gm_maps.maps.len(),
+        1,
+        30.,
+        expanded,
+        gc,
+        |gc, r, c| {
+            node_children(
+                (
+                    class_node_fill,
+                    if select_info.map_editor_level_index == r {
+                        class_node_collapse_item_hover
+                    } else {
+                        class_node_collapse_item_default
+                    },
+                ),
+                gc,
+                (Interaction::None, GridItemInfo(r, c)),
+                |gc| {
+                    node_children(class_node_map_name_style, gc, (), |gc| {
+                        text(
+                            [gm_maps.maps[r].name.as_str()],
+                            class_node_map_name_text,
+                            gc,
+                            MapNameText(r),
+                        );
+                    });
+                },
+            );
+        },
+    );
+}
 ///目前bevy支持的文字组件设置样式确实太垃圾,只能多层嵌套
 pub fn wd_setup_collapse_grid(
     title: &str,
     row: usize,
     col: usize,
     item_height: f32,
+    expanded: bool,
     gc: &mut ChildBuilder,
     children: impl FnMut(&mut ChildBuilder, usize, usize),
 ) {
@@ -75,13 +152,21 @@ pub fn wd_setup_collapse_grid(
                             class_node_fill,
                         ),
                         gc,
-                        (Interaction::None, relate),
+                        //标题为按下状态时会触发展开动画
+                        (
+                            if expanded {
+                                Interaction::Pressed
+                            } else {
+                                Interaction::None
+                            },
+                            relate,
+                        ),
                         |gc| {
                             text(
                                 [GAME_ICON_ARROW_LEFT, " ", title],
                                 class_node_text_title,
                                 gc,
-                                (Animator::default(), relate),
+                                (Animator::default(), relate, Tr::section(title, 2)),
                             );
                         },
                     );
@@ -208,6 +293,23 @@ pub fn wd_node_block(
     }
 }
 
+///图案库中的图案,用四个小格子显示形状
+pub fn wd_node_pattern(gc: &mut ChildBuilder, index: usize) -> Entity {
+    button_children(class_node_pattern, gc, EditorPattern(index), |gc| {
+        for cell in GAME_EDITOR_PATTERNS[index] {
+            if cell == 1 {
+                node(
+                    (class_node_pattern_cell, class_node_pattern_cell_on),
+                    gc,
+                    (),
+                );
+            } else {
+                node(class_node_pattern_cell, gc, ());
+            }
+        }
+    })
+}
+
 pub fn wd_update_node_block(
     mut commands: Commands,
     mut query_change: Query<
@@ -254,19 +356,18 @@ pub fn wd_update_node_block(
 
 pub fn wd_load_game_map(
     gc: &mut ChildBuilder,
-    gm_map: &GameMapCollection,
+    game_map: &GameMap,
     ui_map_select: &UISelectInfo,
     gm_state: &State<GameState>,
+    settings: &GameSettings,
 ) {
+    let in_editor = *gm_state.get() == GameState::UIMapEditor;
+    //游戏中开场的敌人和之后的增援一样按地图设置的顺序出场,不超过敌人总数
+    let mut spawned = 0;
     let mut colider = Colider::new(0, 624., 624.);
     colider.container();
     sprite_children((), gc, colider, |gc| {
-        let index = if *gm_state.get() == GameState::UIMapEditor {
-            ui_map_select.map_editor_level_index
-        } else {
-            ui_map_select.map_index
-        };
-        for block in gm_map.maps[index].to_blocks().iter() {
+        for block in game_map.to_blocks().iter() {
             if block.block == 6 {
                 sprite_sheet(
                     class_sprite_sheet_block,
@@ -274,39 +375,23 @@ pub fn wd_load_game_map(
                     (block.clone(), Colider::new(block.block, 48., 48.)),
                 );
             } else if block.block == 7 {
-                sprite_sheet(
-                    class_sprite_sheet_block,
-                    gc,
-                    (
-                        block.clone(),
-                        Moving::new(GameDirection::Up, 2.),
-                        Player::new_player1(),
-                        Colider::new(block.block, 48., 48.),
-                    ),
-                );
+                wd_sprite_player(gc, block, settings);
             } else if block.block == 8 {
-                sprite_sheet(
-                    class_sprite_sheet_block,
-                    gc,
-                    (
-                        block.clone(),
-                        Moving::new(GameDirection::Up, 2.),
-                        Player::new_player2(),
-                        Colider::new(block.block, 48., 48.),
-                    ),
-                );
+                //单人模式不生成玩家2,编辑器中始终显示
+                if in_editor || ui_map_select.game_mode == GameMode::TwoPlayers {
+                    wd_sprite_player(gc, block, settings);
+                }
             } else if [9, 10, 11].contains(&block.block) {
-                sprite_sheet(
-                    class_sprite_sheet_block,
-                    gc,
-                    (
-                        block.clone(),
-                        Moving::new(GameDirection::Down, 2.),
-                        Player::new_enemy(block.block),
-                        Enemy::default(),
-                        Colider::new(block.block, 48., 48.),
-                    ),
-                );
+                if in_editor {
+                    wd_sprite_enemy(gc, block);
+                } else if spawned < game_map.enemy_total() {
+                    let mut block = *block;
+                    if let Some(enemy) = game_map.enemy_type(spawned) {
+                        block.block = enemy;
+                    }
+                    spawned += 1;
+                    wd_sprite_enemy(gc, &block);
+                }
             } else if [3, 4, 5].contains(&block.block) {
                 wd_sprite_block(gc, block);
             } else {
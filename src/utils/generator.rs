@@ -0,0 +1,206 @@
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::res::{EditorMirror, GameMap, GAME_EDITOR_PATTERNS};
+
+use super::util::a_star;
+
+///敌人出生点的方格(2x2)位置和块的编号
+const ENEMY_SPAWNS: [(usize, usize, usize); 3] = [(0, 0, 10), (0, 6, 9), (0, 12, 11)];
+///玩家出生点的方格位置和块的编号
+const PLAYER_SPAWNS: [(usize, usize, usize); 2] = [(12, 4, 7), (12, 8, 8)];
+///老家的方格位置
+const HOME: (usize, usize) = (12, 6);
+///围墙占用的方格,放围墙前先清空,避免和水、树林拼在同一个方格里
+const FORTRESS_QUADS: [(usize, usize); 5] = [(11, 5), (11, 6), (11, 7), (12, 5), (12, 7)];
+///围墙的格子
+const FORTRESS_CELLS: [(usize, usize); 8] = [
+    (23, 11),
+    (23, 12),
+    (23, 13),
+    (23, 14),
+    (24, 11),
+    (25, 11),
+    (24, 14),
+    (25, 14),
+];
+
+///老家周围的围墙
+#[derive(Default, Clone, Copy, Eq, PartialEq, Debug)]
+pub enum HomeFortress {
+    None,
+    #[default]
+    Brick,
+    Iron,
+}
+
+///按种子和参数随机生成地图,种子和参数相同时生成的地图相同
+#[derive(Clone, Debug)]
+pub struct MapGenerator {
+    pub seed: u64,
+    ///有墙的方格占比
+    pub wall_density: f64,
+    ///墙中铁块的占比
+    pub iron_ratio: f64,
+    ///水和树林的片数,每片2到5个方格
+    pub water_clusters: usize,
+    pub tree_clusters: usize,
+    ///地形的对称方式,与编辑器的镜像画笔相同
+    pub symmetry: EditorMirror,
+    pub fortress: HomeFortress,
+}
+
+impl Default for MapGenerator {
+    fn default() -> Self {
+        MapGenerator {
+            seed: 0,
+            wall_density: 0.35,
+            iron_ratio: 0.15,
+            water_clusters: 2,
+            tree_clusters: 3,
+            symmetry: EditorMirror::Horizontal,
+            fortress: HomeFortress::Brick,
+        }
+    }
+}
+
+impl MapGenerator {
+    pub fn new(seed: u64) -> Self {
+        MapGenerator {
+            seed,
+            ..Default::default()
+        }
+    }
+
+    ///生成地图:墙、水和树林,按对称方式镜像,放上出生点和老家,最后保证每个出生点都能到达老家
+    pub fn generate(&self, name: impl Into<String>) -> GameMap {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut game_map = GameMap::new_blank(name);
+        let map = &mut game_map.map;
+        for row in 0..13 {
+            for col in 0..13 {
+                if rng.gen_bool(self.wall_density.clamp(0., 1.)) {
+                    let block = if rng.gen_bool(self.iron_ratio.clamp(0., 1.)) {
+                        2
+                    } else {
+                        1
+                    };
+                    let pattern = GAME_EDITOR_PATTERNS.choose(&mut rng).unwrap();
+                    set_quad(map, (row, col), pattern.map(|cell| cell * block));
+                }
+            }
+        }
+        for (count, block) in [(self.water_clusters, 5), (self.tree_clusters, 4)] {
+            for _ in 0..count {
+                let mut quad = (rng.gen_range(1..12), rng.gen_range(0..13));
+                for _ in 0..rng.gen_range(2..=5) {
+                    set_quad(map, quad, [block; 4]);
+                    let (dr, dc) = *[(0, 1), (0, -1), (1, 0), (-1, 0)].choose(&mut rng).unwrap();
+                    quad = (
+                        (quad.0 as isize + dr).clamp(1, 11) as usize,
+                        (quad.1 as isize + dc).clamp(0, 12) as usize,
+                    );
+                }
+            }
+        }
+        mirror(map, self.symmetry);
+        for (row, col, block) in ENEMY_SPAWNS.into_iter().chain(PLAYER_SPAWNS) {
+            set_quad(map, (row, col), [block; 4]);
+        }
+        if self.fortress != HomeFortress::None {
+            let block = if self.fortress == HomeFortress::Iron {
+                2
+            } else {
+                1
+            };
+            for quad in FORTRESS_QUADS {
+                set_quad(map, quad, [0; 4]);
+            }
+            for (row, col) in FORTRESS_CELLS {
+                map[row][col] = block;
+            }
+        }
+        set_quad(map, HOME, [6; 4]);
+        //铁围墙是特意放的,打通路线时保留
+        let wall: &[(usize, usize)] = if self.fortress == HomeFortress::Iron {
+            &FORTRESS_CELLS
+        } else {
+            &[]
+        };
+        connect(map, wall);
+        game_map
+    }
+}
+
+///设置方格(2x2)中的四个格子,顺序为左上、右上、左下、右下
+fn set_quad(map: &mut [Vec<usize>], (row, col): (usize, usize), cells: [usize; 4]) {
+    for (i, cell) in cells.into_iter().enumerate() {
+        map[row * 2 + i / 2][col * 2 + i % 2] = cell;
+    }
+}
+
+///把左边镜像到右边、上边镜像到下边;按格子镜像,方格仍然对齐
+fn mirror(map: &mut [Vec<usize>], symmetry: EditorMirror) {
+    let (horizontal, vertical) = symmetry.flips();
+    let size = map.len();
+    if horizontal {
+        for row in map.iter_mut() {
+            let (left, right) = row.split_at_mut(size / 2);
+            for (cell, mirrored) in right.iter_mut().zip(left.iter().rev()) {
+                *cell = *mirrored;
+            }
+        }
+    }
+    if vertical {
+        let (top, bottom) = map.split_at_mut(size / 2);
+        for (row, mirrored) in bottom.iter_mut().zip(top.iter().rev()) {
+            row.clone_from(mirrored);
+        }
+    }
+}
+
+///砖块可以打掉,玩家出生点在游戏中是空地,都当作能通过;open为true时只保留老家
+///wall中的格子是老家的围墙,到了围墙就算到了老家
+fn passable(map: &[Vec<usize>], open: bool, wall: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    map.iter()
+        .enumerate()
+        .map(|(r, row)| {
+            row.iter()
+                .enumerate()
+                .map(|(c, block)| match block {
+                    6 => 6,
+                    _ if wall.contains(&(r, c)) => 0,
+                    1 | 7 | 8 => 0,
+                    _ if open => 0,
+                    _ => *block,
+                })
+                .collect()
+        })
+        .collect()
+}
+
+///出生点到不了老家时,沿忽略地形的路线把铁块换成砖块、清除水,wall中的格子不改
+fn connect(map: &mut [Vec<usize>], wall: &[(usize, usize)]) {
+    let home = (HOME.0 * 2, HOME.1 * 2);
+    for (row, col, _) in ENEMY_SPAWNS.into_iter().chain(PLAYER_SPAWNS) {
+        let start = (row * 2, col * 2);
+        if a_star(&passable(map, false, wall), start, home).is_some() {
+            continue;
+        }
+        let Some(path) = a_star(&passable(map, true, wall), start, home) else {
+            continue;
+        };
+        for (r, c) in path {
+            for (r, c) in [(r, c), (r, c + 1), (r + 1, c), (r + 1, c + 1)] {
+                if wall.contains(&(r, c)) {
+                    continue;
+                }
+                match map[r][c] {
+                    2 => map[r][c] = 1,
+                    //水占用整个方格
+                    5 => set_quad(map, (r / 2, c / 2), [0; 4]),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+use serde_ron::from_str;
+
+use crate::res::{GameSettings, GameSource, Locale, TextFonts, Tr, GAME_I18N_PATH};
+
+///语言文件assets/i18n/<语言>.ron的格式
+#[derive(Deserialize, Default)]
+struct LocaleFile {
+    #[serde(default)]
+    font: Option<String>,
+    ///assets中没有该字体时,在系统字体目录中依次查找文件名包含这些名称的字体
+    #[serde(default)]
+    system_fonts: Vec<String>,
+    strings: HashMap<String, String>,
+}
+
+fn load_locale_file(language: &str) -> LocaleFile {
+    let path = format!("{}/{}.ron", GAME_I18N_PATH, language);
+    match std::fs::read_to_string(&path).map(|file| from_str::<LocaleFile>(&file)) {
+        Ok(Ok(file)) => file,
+        Ok(Err(err)) => {
+            println!("load locale {} error: {}", path, err);
+            LocaleFile::default()
+        }
+        Err(err) => {
+            println!("load locale {} error: {}", path, err);
+            LocaleFile::default()
+        }
+    }
+}
+
+///各系统存放字体的目录
+fn system_font_dirs() -> Vec<PathBuf> {
+    let mut font_dirs: Vec<PathBuf> = [
+        "/usr/share/fonts",
+        "/usr/local/share/fonts",
+        "/System/Library/Fonts",
+        "/Library/Fonts",
+    ]
+    .into_iter()
+    .map(PathBuf::from)
+    .collect();
+    if let Some(windir) = std::env::var_os("WINDIR") {
+        font_dirs.push(PathBuf::from(windir).join("Fonts"));
+    }
+    if let Some(home) = dirs::home_dir() {
+        font_dirs.push(home.join(".fonts"));
+    }
+    font_dirs.extend(dirs::font_dir());
+    font_dirs
+}
+
+///收集目录中的字体文件,子目录最多查找depth层
+fn collect_font_files(dir: &Path, depth: usize, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.is_dir() {
+            if depth > 0 {
+                collect_font_files(&path, depth - 1, files);
+            }
+        } else if path.extension().is_some_and(|ext| {
+            ["ttf", "ttc", "otf"].contains(&ext.to_string_lossy().to_lowercase().as_str())
+        }) {
+            files.push(path);
+        }
+    }
+}
+
+///按names的顺序读取第一个能解析的系统字体
+fn load_system_font(names: &[String]) -> Option<Font> {
+    if names.is_empty() {
+        return None;
+    }
+    let mut files = vec![];
+    for dir in system_font_dirs() {
+        collect_font_files(&dir, 4, &mut files);
+    }
+    names.iter().find_map(|name| {
+        let name = name.to_lowercase();
+        files
+            .iter()
+            .filter(|path| {
+                path.file_name()
+                    .is_some_and(|file| file.to_string_lossy().to_lowercase().contains(&name))
+            })
+            .find_map(|path| Font::try_from_bytes(std::fs::read(path).ok()?).ok())
+    })
+}
+
+///设置中的语言变化时重新读取文字表
+pub fn update_locale(
+    settings: Res<GameSettings>,
+    asset_server: Res<AssetServer>,
+    mut fonts: ResMut<Assets<Font>>,
+    mut locale: ResMut<Locale>,
+) {
+    if locale.language == settings.language {
+        return;
+    }
+    let file = load_locale_file(&settings.language);
+    //字体文件不存在时改用系统字体,都没有时不切换字体,避免文字无法显示
+    let font = match file
+        .font
+        .filter(|font| Path::new("assets").join(font).exists())
+    {
+        Some(font) => Some(asset_server.load(font)),
+        None => load_system_font(&file.system_fonts).map(|font| fonts.add(font)),
+    };
+    *locale = Locale {
+        language: settings.language.clone(),
+        strings: file.strings,
+        font,
+    };
+}
+
+///翻译带有Tr的文字,并在语言需要时把游戏字体替换为该语言的字体
+pub fn update_ui_translate(
+    mut commands: Commands,
+    locale: Res<Locale>,
+    gm_res: Res<GameSource>,
+    mut query_text: Query<(Entity, &mut Text, Option<Ref<Tr>>)>,
+    query_fonts: Query<&TextFonts>,
+) {
+    let replaceable = [
+        Handle::default(),
+        gm_res.font.clone(),
+        gm_res.font_mono.clone(),
+    ];
+    for (entity, mut text, tr) in query_text.iter_mut() {
+        let fonts = query_fonts.get(entity).ok();
+        let tr_changed = tr.as_ref().is_some_and(|tr| tr.is_changed());
+        if !locale.is_changed() && fonts.is_some() && !tr_changed {
+            continue;
+        }
+        if let Some(tr) = tr {
+            if let Some(section) = text.sections.get_mut(tr.section) {
+                section.value = locale.get(&tr.key);
+            }
+        }
+        let fonts = match fonts {
+            Some(fonts) => fonts.0.clone(),
+            None => {
+                let fonts: Vec<Handle<Font>> = text
+                    .sections
+                    .iter()
+                    .map(|section| section.style.font.clone())
+                    .collect();
+                commands.entity(entity).insert(TextFonts(fonts.clone()));
+                fonts
+            }
+        };
+        for (section, font) in text.sections.iter_mut().zip(fonts) {
+            if replaceable.contains(&font) {
+                section.style.font = locale.font.clone().unwrap_or(font);
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::res::{GameDirection, InputDevice, KeysBinding, MenuInput, GAME_GAMEPAD_DEADZONE};
+
+///手柄上对应上下左右的十字键
+const GAMEPAD_DPAD: [GamepadButtonType; 4] = [
+    GamepadButtonType::DPadUp,
+    GamepadButtonType::DPadDown,
+    GamepadButtonType::DPadLeft,
+    GamepadButtonType::DPadRight,
+];
+///手柄上的开火键
+const GAMEPAD_FIRE: [GamepadButtonType; 2] = [GamepadButtonType::South, GamepadButtonType::West];
+
+///统一读取键盘和手柄的输入
+#[derive(SystemParam)]
+pub struct GameInput<'w> {
+    pub keys: Res<'w, ButtonInput<KeyCode>>,
+    pub gamepads: Res<'w, Gamepads>,
+    pub gamepad_buttons: Res<'w, ButtonInput<GamepadButton>>,
+    pub gamepad_axes: Res<'w, Axis<GamepadAxis>>,
+}
+
+impl<'w> GameInput<'w> {
+    ///设备当前按住的方向,顺序与GameDirection::ALL一致
+    pub fn directions(&self, device: InputDevice, keys: &KeysBinding) -> [bool; 4] {
+        match device {
+            InputDevice::Keyboard => {
+                let keys = keys.keys();
+                [0, 1, 2, 3].map(|i| self.keys.pressed(keys[i]))
+            }
+            InputDevice::Gamepad(id) => self.gamepad_directions(Gamepad::new(id)),
+        }
+    }
+
+    pub fn fire(&self, device: InputDevice, keys: &KeysBinding) -> bool {
+        match device {
+            InputDevice::Keyboard => self.keys.pressed(keys.fire),
+            InputDevice::Gamepad(id) => GAMEPAD_FIRE.iter().any(|&button| {
+                self.gamepad_buttons
+                    .pressed(GamepadButton::new(Gamepad::new(id), button))
+            }),
+        }
+    }
+
+    ///十字键和左摇杆合并后的方向
+    pub fn gamepad_directions(&self, gamepad: Gamepad) -> [bool; 4] {
+        let stick = self.stick_directions(gamepad);
+        let mut directions = [false; 4];
+        for (i, button) in GAMEPAD_DPAD.into_iter().enumerate() {
+            directions[i] = stick[i]
+                || self
+                    .gamepad_buttons
+                    .pressed(GamepadButton::new(gamepad, button));
+        }
+        directions
+    }
+
+    ///左摇杆超过死区后按主轴换算成方向
+    pub fn stick_directions(&self, gamepad: Gamepad) -> [bool; 4] {
+        let axis = |axis_type| {
+            self.gamepad_axes
+                .get(GamepadAxis::new(gamepad, axis_type))
+                .unwrap_or(0.)
+        };
+        let stick = Vec2::new(
+            axis(GamepadAxisType::LeftStickX),
+            axis(GamepadAxisType::LeftStickY),
+        );
+        let direction = stick_direction(stick, GAME_GAMEPAD_DEADZONE);
+        GameDirection::ALL.map(|d| Some(d) == direction)
+    }
+
+    fn any_gamepad_just_pressed(&self, button: GamepadButtonType) -> bool {
+        self.gamepads.iter().any(|gamepad| {
+            self.gamepad_buttons
+                .just_pressed(GamepadButton::new(gamepad, button))
+        })
+    }
+}
+
+///摇杆的方向,在死区内时为None,斜向推动时取偏移较大的轴
+pub fn stick_direction(stick: Vec2, deadzone: f32) -> Option<GameDirection> {
+    if stick.length() < deadzone {
+        None
+    } else if stick.x.abs() > stick.y.abs() {
+        Some(if stick.x > 0. {
+            GameDirection::Right
+        } else {
+            GameDirection::Left
+        })
+    } else if stick.y > 0. {
+        Some(GameDirection::Up)
+    } else {
+        Some(GameDirection::Down)
+    }
+}
+
+///按住的方向入栈,松开的方向出栈,栈顶为最后按下的方向,模拟输入也保持和键盘一样的语义
+pub fn apply_directions(direction_stack: &mut Vec<GameDirection>, held: [bool; 4]) {
+    for (direction, held) in GameDirection::ALL.into_iter().zip(held) {
+        if !held {
+            direction_stack.retain(|&x| x != direction);
+        } else if !direction_stack.contains(&direction) {
+            direction_stack.push(direction);
+        }
+    }
+}
+
+///每帧汇总键盘和手柄的菜单操作
+pub fn update_menu_input(input: GameInput, mut menu_input: ResMut<MenuInput>) {
+    let keys = &input.keys;
+    let mut stick = [false; 4];
+    for gamepad in input.gamepads.iter() {
+        for (held, pushed) in stick.iter_mut().zip(input.stick_directions(gamepad)) {
+            *held |= pushed;
+        }
+    }
+    //摇杆只在刚推过死区时触发一次
+    let stick_pressed: [bool; 4] = [0, 1, 2, 3].map(|i| stick[i] && !menu_input.stick[i]);
+    let pressed = |i: usize, key: KeyCode, arrow: KeyCode| {
+        keys.just_pressed(key)
+            || keys.just_pressed(arrow)
+            || stick_pressed[i]
+            || input.any_gamepad_just_pressed(GAMEPAD_DPAD[i])
+    };
+    *menu_input = MenuInput {
+        up: pressed(0, KeyCode::KeyW, KeyCode::ArrowUp),
+        down: pressed(1, KeyCode::KeyS, KeyCode::ArrowDown),
+        left: pressed(2, KeyCode::KeyA, KeyCode::ArrowLeft),
+        right: pressed(3, KeyCode::KeyD, KeyCode::ArrowRight),
+        confirm: keys.just_pressed(KeyCode::Enter)
+            || input.any_gamepad_just_pressed(GamepadButtonType::South),
+        back: keys.just_pressed(KeyCode::Escape)
+            || input.any_gamepad_just_pressed(GamepadButtonType::East)
+            || input.any_gamepad_just_pressed(GamepadButtonType::Start),
+        stick,
+    };
+}
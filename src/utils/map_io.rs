@@ -0,0 +1,426 @@
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_ron::ser::{to_string_pretty, PrettyConfig};
+use thiserror::Error;
+
+use super::ron::{RonAsset, RonLoaderError};
+use crate::res::{GameMap, GameMapCollection, MapError, GAME_POWER_UP_DROP_RATE};
+
+///文本地图中每个格子使用的字符,下标为块的编号
+pub const ASCII_BLOCKS: [char; 12] = ['.', '#', '@', '-', '%', '~', 'H', '1', '2', 'E', 'F', 'G'];
+///地图的行数和列数
+const MAP_SIZE: usize = 26;
+///Tiled中图块id的高位是翻转标记
+const TILED_FLIP_FLAGS: u32 = 0xE000_0000;
+
+///地图文件格式,按扩展名区分
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum MapFormat {
+    ///游戏使用的map.ron,可以包含多张地图
+    Ron,
+    ///文本格式,可以包含多张地图
+    Ascii,
+    ///Tiled的JSON地图,一个文件一张地图
+    TiledJson,
+}
+
+impl MapFormat {
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        match path.as_ref().extension()?.to_str()? {
+            "ron" => Some(MapFormat::Ron),
+            "txt" => Some(MapFormat::Ascii),
+            "json" | "tmj" => Some(MapFormat::TiledJson),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum MapFormatError {
+    #[error("unknown map format: {0}")]
+    UnknownFormat(String),
+    #[error("line {line}: {message}")]
+    Ascii { line: usize, message: String },
+    #[error("tiled map: {0}")]
+    Tiled(String),
+    #[error("tiled json holds one map, found {0}")]
+    TiledMapCount(usize),
+    #[error("no map in the file")]
+    NoMap,
+    #[error("map index {index} is out of range, found {count} maps")]
+    MapIndex { index: usize, count: usize },
+    #[error("{name}: {error}")]
+    Invalid { name: String, error: MapError },
+    #[error("Could not read or write the file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    RonError(#[from] RonLoaderError),
+    #[error("Could not serialize RON: {0}")]
+    RonWriteError(#[from] serde_ron::Error),
+    #[error("Could not parse JSON: {0}")]
+    JsonError(#[from] serde_json::Error),
+}
+
+///检查每张地图,返回第一张不合法地图的错误
+pub fn validate_maps(maps: &[GameMap]) -> Result<(), MapFormatError> {
+    for game_map in maps.iter() {
+        game_map
+            .validate()
+            .map_err(|error| MapFormatError::Invalid {
+                name: game_map.name.clone(),
+                error,
+            })?;
+    }
+    Ok(())
+}
+
+pub fn read_maps(text: &str, format: MapFormat) -> Result<Vec<GameMap>, MapFormatError> {
+    match format {
+        MapFormat::Ron => Ok(GameMapCollection::from_ron(text.as_bytes())?.maps),
+        MapFormat::Ascii => from_ascii(text),
+        MapFormat::TiledJson => Ok(vec![from_tiled_json(text)?]),
+    }
+}
+
+pub fn write_maps(maps: &[GameMap], format: MapFormat) -> Result<String, MapFormatError> {
+    match format {
+        MapFormat::Ron => {
+            let collection = GameMapCollection {
+                maps: maps.to_vec(),
+                ..Default::default()
+            };
+            Ok(to_string_pretty(
+                &collection,
+                PrettyConfig::new().depth_limit(4),
+            )?)
+        }
+        MapFormat::Ascii => Ok(maps.iter().map(to_ascii).collect::<Vec<_>>().join("\n")),
+        MapFormat::TiledJson => match maps {
+            [game_map] => to_tiled_json(game_map),
+            _ => Err(MapFormatError::TiledMapCount(maps.len())),
+        },
+    }
+}
+
+///按扩展名读取地图文件
+pub fn load_maps(path: impl AsRef<Path>) -> Result<Vec<GameMap>, MapFormatError> {
+    let path = path.as_ref();
+    let format = MapFormat::from_path(path)
+        .ok_or_else(|| MapFormatError::UnknownFormat(path.display().to_string()))?;
+    read_maps(&fs::read_to_string(path)?, format)
+}
+
+///读取地图包,地图包是带名称、作者和版本的map.ron格式文件
+pub fn load_map_pack(path: impl AsRef<Path>) -> Result<GameMapCollection, MapFormatError> {
+    let mut pack = GameMapCollection::from_ron(&fs::read(path)?)?;
+    if pack.maps.is_empty() {
+        return Err(MapFormatError::NoMap);
+    }
+    for game_map in pack.maps.iter_mut() {
+        game_map.init_fixed();
+    }
+    validate_maps(&pack.maps)?;
+    Ok(pack)
+}
+
+///按扩展名写入地图文件
+pub fn export_maps(path: impl AsRef<Path>, maps: &[GameMap]) -> Result<(), MapFormatError> {
+    let path = path.as_ref();
+    let format = MapFormat::from_path(path)
+        .ok_or_else(|| MapFormatError::UnknownFormat(path.display().to_string()))?;
+    fs::write(path, write_maps(maps, format)?)?;
+    Ok(())
+}
+
+/* ---------------------- */
+
+///文本格式:先写地图信息,再写26行、每行26个字符的格子,多张地图之间空一行,`;`开头的行为注释
+pub fn to_ascii(game_map: &GameMap) -> String {
+    let roster = game_map.enemy_roster;
+    let mut lines = vec![
+        format!("name: {}", game_map.name),
+        format!("author: {}", game_map.author),
+        format!("enemies: {} {} {}", roster[0], roster[1], roster[2]),
+        format!("time_limit: {}", game_map.time_limit),
+        format!("drop_rate: {}", game_map.power_up_drop_rate),
+        format!("music: {}", game_map.music),
+    ];
+    //空的信息不留行尾空格
+    for line in lines.iter_mut() {
+        line.truncate(line.trim_end().len());
+    }
+    for row in game_map.map.iter() {
+        lines.push(
+            row.iter()
+                .map(|block| ASCII_BLOCKS.get(*block).copied().unwrap_or('?'))
+                .collect(),
+        );
+    }
+    lines.join("\n") + "\n"
+}
+
+///读取文本格式,每张地图从name行开始
+pub fn from_ascii(text: &str) -> Result<Vec<GameMap>, MapFormatError> {
+    let mut maps: Vec<(usize, GameMap)> = vec![];
+    for (index, line) in text.lines().enumerate() {
+        let line_no = index + 1;
+        let error = |message: String| MapFormatError::Ascii {
+            line: line_no,
+            message,
+        };
+        let line = line.trim_end();
+        if line.trim().is_empty() || line.starts_with(';') {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            let value = value.trim();
+            if key.trim() == "name" {
+                maps.push((line_no, GameMap::new_blank(value)));
+                maps.last_mut().unwrap().1.map.clear();
+                continue;
+            }
+            let Some((_, game_map)) = maps.last_mut() else {
+                return Err(error("map must start with a name line".into()));
+            };
+            let number = |value: &str| {
+                value
+                    .parse::<u32>()
+                    .map_err(|_| error(format!("{} is not a number", value)))
+            };
+            match key.trim() {
+                "author" => game_map.author = value.into(),
+                "music" => game_map.music = value.into(),
+                "time_limit" => game_map.time_limit = number(value)?,
+                "drop_rate" => game_map.power_up_drop_rate = number(value)?,
+                "enemies" => {
+                    let roster = value
+                        .split_whitespace()
+                        .map(|value| number(value).map(|n| n as usize))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    game_map.enemy_roster = roster
+                        .try_into()
+                        .map_err(|_| error("enemies needs three numbers".into()))?;
+                }
+                key => return Err(error(format!("unknown key {}", key))),
+            }
+            continue;
+        }
+        let Some((_, game_map)) = maps.last_mut() else {
+            return Err(error("map must start with a name line".into()));
+        };
+        let row = line
+            .chars()
+            .map(|c| {
+                ASCII_BLOCKS
+                    .iter()
+                    .position(|block| *block == c)
+                    .ok_or_else(|| error(format!("unknown block '{}'", c)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if row.len() != MAP_SIZE {
+            return Err(error(format!("row must have {} blocks", MAP_SIZE)));
+        }
+        if game_map.map.len() == MAP_SIZE {
+            return Err(error(format!("map must have {} rows", MAP_SIZE)));
+        }
+        game_map.map.push(row);
+    }
+    for (line, game_map) in maps.iter() {
+        if game_map.map.len() != MAP_SIZE {
+            return Err(MapFormatError::Ascii {
+                line: *line,
+                message: format!("map must have {} rows", MAP_SIZE),
+            });
+        }
+    }
+    Ok(maps.into_iter().map(|(_, game_map)| game_map).collect())
+}
+
+/* ---------------------- */
+
+#[derive(Deserialize, Serialize)]
+struct TiledMap {
+    #[serde(rename = "type", default)]
+    kind: String,
+    #[serde(default)]
+    orientation: String,
+    width: usize,
+    height: usize,
+    #[serde(default)]
+    tilewidth: u32,
+    #[serde(default)]
+    tileheight: u32,
+    #[serde(default)]
+    infinite: bool,
+    layers: Vec<TiledLayer>,
+    #[serde(default)]
+    tilesets: Vec<TiledTileset>,
+    #[serde(default)]
+    properties: Vec<TiledProperty>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct TiledLayer {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    width: usize,
+    #[serde(default)]
+    height: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encoding: Option<String>,
+    #[serde(default)]
+    data: Vec<u32>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct TiledTileset {
+    firstgid: u32,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    tilewidth: u32,
+    #[serde(default)]
+    tileheight: u32,
+    #[serde(default)]
+    tilecount: u32,
+    #[serde(default)]
+    columns: u32,
+}
+
+#[derive(Deserialize, Serialize)]
+struct TiledProperty {
+    name: String,
+    #[serde(rename = "type", default)]
+    kind: String,
+    value: Value,
+}
+
+impl TiledProperty {
+    fn new(name: &str, value: impl Into<Value>) -> Self {
+        let value = value.into();
+        let kind = if value.is_number() { "int" } else { "string" };
+        TiledProperty {
+            name: name.into(),
+            kind: kind.into(),
+            value,
+        }
+    }
+}
+
+///Tiled地图:一个26x26的图块层,图块id为块的编号,空地为0;地图信息写在自定义属性中
+pub fn to_tiled_json(game_map: &GameMap) -> Result<String, MapFormatError> {
+    let roster = game_map.enemy_roster;
+    let tiled = TiledMap {
+        kind: "map".into(),
+        orientation: "orthogonal".into(),
+        width: MAP_SIZE,
+        height: MAP_SIZE,
+        tilewidth: 24,
+        tileheight: 24,
+        infinite: false,
+        layers: vec![TiledLayer {
+            kind: "tilelayer".into(),
+            name: "blocks".into(),
+            width: MAP_SIZE,
+            height: MAP_SIZE,
+            encoding: None,
+            data: game_map.map.iter().flatten().map(|b| *b as u32).collect(),
+        }],
+        tilesets: vec![TiledTileset {
+            firstgid: 1,
+            name: "blocks".into(),
+            tilewidth: 24,
+            tileheight: 24,
+            tilecount: (ASCII_BLOCKS.len() - 1) as u32,
+            columns: (ASCII_BLOCKS.len() - 1) as u32,
+        }],
+        properties: vec![
+            TiledProperty::new("name", game_map.name.as_str()),
+            TiledProperty::new("author", game_map.author.as_str()),
+            TiledProperty::new("enemy1", roster[0]),
+            TiledProperty::new("enemy2", roster[1]),
+            TiledProperty::new("enemy3", roster[2]),
+            TiledProperty::new("time_limit", game_map.time_limit),
+            TiledProperty::new("drop_rate", game_map.power_up_drop_rate),
+            TiledProperty::new("music", game_map.music.as_str()),
+        ],
+    };
+    Ok(serde_json::to_string_pretty(&tiled)?)
+}
+
+pub fn from_tiled_json(text: &str) -> Result<GameMap, MapFormatError> {
+    let tiled: TiledMap = serde_json::from_str(text)?;
+    if tiled.width != MAP_SIZE || tiled.height != MAP_SIZE {
+        return Err(MapFormatError::Tiled(format!(
+            "map size must be {}x{}",
+            MAP_SIZE, MAP_SIZE
+        )));
+    }
+    let layer = tiled
+        .layers
+        .iter()
+        .find(|layer| layer.kind == "tilelayer")
+        .ok_or_else(|| MapFormatError::Tiled("no tile layer".into()))?;
+    if layer
+        .encoding
+        .as_deref()
+        .is_some_and(|encoding| encoding != "csv")
+    {
+        return Err(MapFormatError::Tiled(
+            "tile layer must use csv encoding".into(),
+        ));
+    }
+    if layer.data.len() != MAP_SIZE * MAP_SIZE {
+        return Err(MapFormatError::Tiled(format!(
+            "tile layer must have {} tiles",
+            MAP_SIZE * MAP_SIZE
+        )));
+    }
+    //图块id减去所在图块集的firstgid后加1就是块的编号
+    let block = |gid: u32| {
+        let gid = gid & !TILED_FLIP_FLAGS;
+        if gid == 0 {
+            return 0;
+        }
+        let firstgid = tiled
+            .tilesets
+            .iter()
+            .map(|tileset| tileset.firstgid)
+            .filter(|firstgid| *firstgid <= gid)
+            .max()
+            .unwrap_or(1);
+        (gid - firstgid + 1) as usize
+    };
+    let mut game_map = GameMap::new_blank("");
+    game_map.map = layer
+        .data
+        .chunks(MAP_SIZE)
+        .map(|row| row.iter().map(|gid| block(*gid)).collect())
+        .collect();
+    let number = |value: &Value| value.as_u64().unwrap_or_default();
+    for property in tiled.properties.iter() {
+        let text = property.value.as_str().unwrap_or_default().to_string();
+        match property.name.as_str() {
+            "name" => game_map.name = text,
+            "author" => game_map.author = text,
+            "music" => game_map.music = text,
+            "enemy1" => game_map.enemy_roster[0] = number(&property.value) as usize,
+            "enemy2" => game_map.enemy_roster[1] = number(&property.value) as usize,
+            "enemy3" => game_map.enemy_roster[2] = number(&property.value) as usize,
+            "time_limit" => game_map.time_limit = number(&property.value) as u32,
+            "drop_rate" => {
+                game_map.power_up_drop_rate = property
+                    .value
+                    .as_u64()
+                    .map_or(GAME_POWER_UP_DROP_RATE, |rate| rate as u32)
+            }
+            _ => {}
+        }
+    }
+    Ok(game_map)
+}
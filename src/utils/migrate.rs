@@ -0,0 +1,141 @@
+use std::fmt;
+
+use serde::{
+    de::{Error, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use serde_ron::{de::from_bytes, Value};
+
+use super::ron::{RonAsset, RonLoaderError};
+use crate::res::{GameMap, GameMapCollection, MapError, GAME_MAP_VERSION};
+
+///地图的行数和列数
+const MAP_SIZE: usize = 26;
+///最大的块编号
+const MAX_BLOCK: usize = 11;
+
+///version字段可能是文件格式的版本,也可能是版本0的地图包自己的版本
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawVersion {
+    Number(u32),
+    Text(String),
+}
+
+///只读取version,其它字段忽略
+#[derive(Deserialize)]
+#[serde(rename = "GameMapCollection")]
+struct VersionProbe {
+    #[serde(default, deserialize_with = "deserialize_version")]
+    version: u32,
+}
+
+fn deserialize_version<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    match RawVersion::deserialize(deserializer)? {
+        RawVersion::Number(version) if version > GAME_MAP_VERSION => {
+            Err(D::Error::custom(format_args!(
+                "map version {version} is newer than supported version {GAME_MAP_VERSION}"
+            )))
+        }
+        RawVersion::Number(version) => Ok(version),
+        RawVersion::Text(_) => Ok(0),
+    }
+}
+
+fn deserialize_pack_version<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<String, D::Error> {
+    match RawVersion::deserialize(deserializer)? {
+        RawVersion::Number(_) => Ok(String::new()),
+        RawVersion::Text(version) => Ok(version),
+    }
+}
+
+///版本0:没有version字段,地图包的版本也保存在version中
+#[derive(Deserialize)]
+#[serde(rename = "GameMapCollection")]
+struct GameMapCollectionV0 {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    author: String,
+    #[serde(default, deserialize_with = "deserialize_pack_version")]
+    version: String,
+    maps: Vec<GameMap>,
+}
+
+impl From<GameMapCollectionV0> for GameMapCollection {
+    fn from(old: GameMapCollectionV0) -> Self {
+        GameMapCollection {
+            version: GAME_MAP_VERSION,
+            name: old.name,
+            author: old.author,
+            pack_version: old.version,
+            maps: old.maps,
+        }
+    }
+}
+
+impl RonAsset for GameMapCollection {
+    ///先检查语法和版本,再按文件版本的结构读取并升级到当前版本
+    fn from_ron(bytes: &[u8]) -> Result<Self, RonLoaderError> {
+        from_bytes::<Value>(bytes)?;
+        let probe = from_bytes::<VersionProbe>(bytes).map_err(|error| {
+            RonLoaderError::UnsupportedVersion {
+                message: error.code.to_string(),
+                position: error.position,
+            }
+        })?;
+        let collection = match probe.version {
+            0 => from_bytes::<GameMapCollectionV0>(bytes).map(GameMapCollection::from),
+            _ => from_bytes::<GameMapCollection>(bytes),
+        };
+        collection.map_err(RonLoaderError::schema)
+    }
+}
+
+///读取地图的格子,每读完一行检查长度和块编号,出错时的位置在这一行之后
+pub fn deserialize_grid<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Vec<usize>>, D::Error> {
+    struct GridVisitor;
+
+    impl<'de> Visitor<'de> for GridVisitor {
+        type Value = Vec<Vec<usize>>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(formatter, "{MAP_SIZE} rows of {MAP_SIZE} blocks")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut grid = Vec::with_capacity(MAP_SIZE);
+            while let Some(row) = seq.next_element::<Vec<usize>>()? {
+                let index = grid.len();
+                if index == MAP_SIZE {
+                    return Err(A::Error::invalid_length(index + 1, &self));
+                }
+                if row.len() != MAP_SIZE {
+                    return Err(A::Error::custom(format_args!(
+                        "row {index} has {} blocks, {}",
+                        row.len(),
+                        MapError::Size
+                    )));
+                }
+                if let Some(col) = row.iter().position(|block| *block > MAX_BLOCK) {
+                    return Err(A::Error::custom(MapError::UnknownBlock {
+                        row: index,
+                        col,
+                        block: row[col],
+                    }));
+                }
+                grid.push(row);
+            }
+            if grid.len() != MAP_SIZE {
+                return Err(A::Error::invalid_length(grid.len(), &self));
+            }
+            Ok(grid)
+        }
+    }
+
+    deserializer.deserialize_seq(GridVisitor)
+}
@@ -2,4 +2,10 @@ pub mod animate;
 pub mod class;
 pub mod ron;
 pub mod widget;
-pub mod util;
\ No newline at end of file
+pub mod util;
+pub mod input;
+pub mod i18n;
+pub mod map_io;
+pub mod generator;
+pub mod migrate;
+pub mod share_code;
@@ -4,7 +4,7 @@ use bevy::{
     utils::BoxedFuture,
 };
 use serde::Deserialize;
-use serde_ron::de::from_bytes;
+use serde_ron::{de::from_bytes, error::Position};
 use std::marker::PhantomData;
 use thiserror::Error;
 #[derive(Default)]
@@ -25,12 +25,32 @@ pub enum RonLoaderError {
     /// A [RON Error](serde_ron::error::SpannedError)
     #[error("Could not parse RON: {0}")]
     RonError(#[from] serde_ron::error::SpannedError),
+    /// A file version newer than the game supports, or a version that is not a number
+    #[error("Unsupported version at {position}: {message}")]
+    UnsupportedVersion { message: String, position: Position },
+    /// Valid RON that does not match the asset's schema
+    #[error("Schema violation at {position}: {message}")]
+    Schema { message: String, position: Position },
 }
 
-impl<A> AssetLoader for RonAssetLoader<A>
-where
-    for<'de> A: Asset + Deserialize<'de>,
-{
+impl RonLoaderError {
+    ///语法检查通过后的解析错误都是结构不符合
+    pub fn schema(error: serde_ron::error::SpannedError) -> Self {
+        RonLoaderError::Schema {
+            message: error.code.to_string(),
+            position: error.position,
+        }
+    }
+}
+
+///RonAssetPlugin加载的资源,文件格式有版本时实现from_ron升级旧文件
+pub trait RonAsset: Asset + for<'de> Deserialize<'de> {
+    fn from_ron(bytes: &[u8]) -> Result<Self, RonLoaderError> {
+        Ok(from_bytes::<Self>(bytes)?)
+    }
+}
+
+impl<A: RonAsset> AssetLoader for RonAssetLoader<A> {
     type Asset = A;
     type Settings = ();
     type Error = RonLoaderError;
@@ -44,8 +64,7 @@ where
         Box::pin(async move {
             let mut bytes = Vec::new();
             reader.read_to_end(&mut bytes).await?;
-            let asset = from_bytes::<A>(&bytes)?;
-            Ok(asset)
+            A::from_ron(&bytes)
         })
     }
 
@@ -54,10 +73,7 @@ where
     }
 }
 
-impl<A> Plugin for RonAssetPlugin<A>
-where
-    for<'de> A: Asset + Deserialize<'de>,
-{
+impl<A: RonAsset> Plugin for RonAssetPlugin<A> {
     fn build(&self, app: &mut App) {
         app.init_asset::<A>()
             .register_asset_loader(RonAssetLoader::<A> {
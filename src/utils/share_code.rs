@@ -0,0 +1,196 @@
+//! 分享地图用的短代码:地图信息按字节写入,格子按行展开后做游程编码,每段为4位块编号加上
+//! 长度,长度每4位一组(3位数值、1位表示还有下一组),最后转成URL安全的base64
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use thiserror::Error;
+
+use crate::res::{GameMap, MapError};
+
+///代码格式的版本,写在第一个字节
+pub const SHARE_CODE_VERSION: u8 = 1;
+///地图的行数和列数
+const MAP_SIZE: usize = 26;
+
+#[derive(Debug, Error)]
+pub enum ShareCodeError {
+    #[error("map code is not valid base64: {0}")]
+    Base64(#[from] base64::DecodeError),
+    #[error("unsupported map code version {0}")]
+    Version(u8),
+    #[error("map code is incomplete")]
+    Truncated,
+    #[error("map code text is not valid UTF-8")]
+    Text(#[from] std::string::FromUtf8Error),
+    #[error("map code has more than {} blocks", MAP_SIZE * MAP_SIZE)]
+    Overflow,
+    #[error("{0}")]
+    Invalid(#[from] MapError),
+}
+
+///按位写入,从每个字节的高位开始
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: usize, bits: usize) {
+        for i in (0..bits).rev() {
+            if self.len.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            if value >> i & 1 == 1 {
+                *self.bytes.last_mut().unwrap() |= 0x80 >> (self.len % 8);
+            }
+            self.len += 1;
+        }
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl BitReader<'_> {
+    fn read(&mut self, bits: usize) -> Result<usize, ShareCodeError> {
+        let mut value = 0;
+        for _ in 0..bits {
+            let byte = self
+                .bytes
+                .get(self.pos / 8)
+                .ok_or(ShareCodeError::Truncated)?;
+            value = value << 1 | (byte >> (7 - self.pos % 8) & 1) as usize;
+            self.pos += 1;
+        }
+        Ok(value)
+    }
+}
+
+///按字节读取地图信息
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ShareCodeError> {
+        if self.bytes.len() < len {
+            return Err(ShareCodeError::Truncated);
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn varint(&mut self) -> Result<usize, ShareCodeError> {
+        let mut value = 0;
+        for shift in (0..usize::BITS).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ShareCodeError::Overflow)
+    }
+
+    fn text(&mut self) -> Result<String, ShareCodeError> {
+        let len = self.varint()?;
+        Ok(String::from_utf8(self.take(len)?.to_vec())?)
+    }
+}
+
+///每字节7位的变长整数,小的数只占一个字节
+fn write_varint(bytes: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn write_text(bytes: &mut Vec<u8>, text: &str) {
+    write_varint(bytes, text.len());
+    bytes.extend_from_slice(text.as_bytes());
+}
+
+///把地图编码成分享代码,包括名称和地图信息
+pub fn encode_map(game_map: &GameMap) -> String {
+    let mut bytes = vec![SHARE_CODE_VERSION];
+    write_text(&mut bytes, &game_map.name);
+    write_text(&mut bytes, &game_map.author);
+    write_text(&mut bytes, &game_map.music);
+    for count in game_map.enemy_roster {
+        write_varint(&mut bytes, count);
+    }
+    write_varint(&mut bytes, game_map.time_limit as usize);
+    write_varint(&mut bytes, game_map.power_up_drop_rate as usize);
+    let mut bits = BitWriter::default();
+    let mut cells = game_map.map.iter().flatten().peekable();
+    while let Some(block) = cells.next() {
+        let mut run = 1;
+        while cells.next_if_eq(&block).is_some() {
+            run += 1;
+        }
+        bits.write(*block, 4);
+        let mut rest = run - 1;
+        loop {
+            let more = rest >= 8;
+            bits.write(rest & 7 | (more as usize) << 3, 4);
+            rest >>= 3;
+            if !more {
+                break;
+            }
+        }
+    }
+    bytes.extend(bits.bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+///解析分享代码,解析后检查地图是否合法
+pub fn decode_map(code: &str) -> Result<GameMap, ShareCodeError> {
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    let bytes = URL_SAFE_NO_PAD.decode(code.trim_end_matches('='))?;
+    let mut reader = ByteReader { bytes: &bytes };
+    let version = reader.take(1)?[0];
+    if version != SHARE_CODE_VERSION {
+        return Err(ShareCodeError::Version(version));
+    }
+    let mut game_map = GameMap::new_blank(reader.text()?);
+    game_map.author = reader.text()?;
+    game_map.music = reader.text()?;
+    for count in game_map.enemy_roster.iter_mut() {
+        *count = reader.varint()?;
+    }
+    game_map.time_limit = u32::try_from(reader.varint()?).map_err(|_| ShareCodeError::Overflow)?;
+    game_map.power_up_drop_rate =
+        u32::try_from(reader.varint()?).map_err(|_| ShareCodeError::Overflow)?;
+    let mut bits = BitReader {
+        bytes: reader.bytes,
+        pos: 0,
+    };
+    let mut cells = Vec::with_capacity(MAP_SIZE * MAP_SIZE);
+    while cells.len() < MAP_SIZE * MAP_SIZE {
+        let block = bits.read(4)?;
+        let mut run = 0;
+        for shift in (0..).step_by(3) {
+            let group = bits.read(4)?;
+            run |= (group & 7) << shift;
+            if group & 8 == 0 {
+                break;
+            }
+            //下一组会超出一张地图的格子数,不用再读
+            if run >= MAP_SIZE * MAP_SIZE || shift + 3 >= usize::BITS as usize {
+                return Err(ShareCodeError::Overflow);
+            }
+        }
+        if cells.len() + run + 1 > MAP_SIZE * MAP_SIZE {
+            return Err(ShareCodeError::Overflow);
+        }
+        cells.extend(std::iter::repeat_n(block, run + 1));
+    }
+    game_map.map = cells.chunks(MAP_SIZE).map(<[usize]>::to_vec).collect();
+    game_map.validate()?;
+    Ok(game_map)
+}
@@ -1,17 +1,28 @@
-use std::collections::{BinaryHeap, HashMap};
 use bevy::prelude::*;
 use rand::seq::SliceRandom;
-use serde_ron::to_string;
+use serde_ron::{
+    from_str,
+    ser::{to_string_pretty, PrettyConfig},
+    to_string,
+};
+use std::{
+    collections::{BinaryHeap, HashMap, VecDeque},
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
 
+use super::map_io::load_map_pack;
 use crate::res::{
-    GameDirection, GameMapCollection, GAME_AREA_BLOCK, GAME_AREA_BLOCK_FOUR, INITIAL_SETTINGS,
+    EditorStampLibrary, GameDirection, GameMap, GameMapCollection, GameProgress, GameSettings,
+    MapError, MapPack, MapPackSource, GAME_AREA_BLOCK, GAME_AREA_BLOCK_FOUR, GAME_MAP_BACKUP_COUNT,
+    GAME_MAP_PACK_PATH, GAME_PROGRESS_PATH, GAME_SETTINGS_PATH, GAME_STAMPS_PATH,
+    GAME_USER_MAP_PACK_FILE, GAME_USER_MAP_PACK_PATH,
 };
-///将鼠标在屏幕上的坐标转换为世界坐标
-pub fn vec2_to_transform_pos(pos: Vec2) -> (f32, f32) {
-    (
-        pos.x - INITIAL_SETTINGS.win_resolution.0 / 2.,
-        INITIAL_SETTINGS.win_resolution.1 / 2. - pos.y,
-    )
+///将鼠标在窗口上的坐标转换为世界坐标,window_size为窗口当前的逻辑大小
+pub fn vec2_to_transform_pos(pos: Vec2, window_size: Vec2) -> (f32, f32) {
+    (pos.x - window_size.x / 2., window_size.y / 2. - pos.y)
 }
 
 pub fn is_four(type_index: usize) -> bool {
@@ -29,10 +40,187 @@ pub fn is_small(type_index: usize) -> bool {
 pub fn is_same_size_block(a: usize, b: usize) -> bool {
     (is_four(a) && is_four(b)) || (is_small(a) && is_small(b))
 }
+///地图从from移动到to后,原来在index的地图的新位置
+pub fn moved_index(index: usize, from: usize, to: usize) -> usize {
+    if index == from {
+        to
+    } else if from < index && index <= to {
+        index - 1
+    } else if to <= index && index < from {
+        index + 1
+    } else {
+        index
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum MapSaveError {
+    #[error("{name}: {error}")]
+    Invalid { name: String, error: MapError },
+    #[error("Could not write the file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not serialize RON: {0}")]
+    RonError(#[from] serde_ron::Error),
+}
+
+///检查全部地图后写入临时文件再替换地图文件,替换前把旧文件轮换为备份
+pub fn save_map(path: &Path, map: &GameMapCollection) -> Result<(), MapSaveError> {
+    for game_map in map.maps.iter() {
+        game_map.validate().map_err(|error| MapSaveError::Invalid {
+            name: game_map.name.clone(),
+            error,
+        })?;
+    }
+    //地图的每一行单独一行,方便查看和比较
+    let config = PrettyConfig::new().depth_limit(4);
+    let map_str = to_string_pretty(map, config)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let temp_path = format!("{}.tmp", path.display());
+    let mut file = fs::File::create(&temp_path)?;
+    file.write_all(map_str.as_bytes())?;
+    file.sync_all()?;
+    drop(file);
+    rotate_map_backups(path)?;
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+fn rotate_map_backups(path: &Path) -> std::io::Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let backup_path = |index: usize| format!("{}.bak{}", path.display(), index);
+    for index in (1..GAME_MAP_BACKUP_COUNT).rev() {
+        if Path::new(&backup_path(index)).exists() {
+            fs::rename(backup_path(index), backup_path(index + 1))?;
+        }
+    }
+    //复制而不是移动,保证替换前地图文件一直存在
+    fs::copy(path, backup_path(1))?;
+    Ok(())
+}
+
+///用户地图包的目录,没有系统数据目录时放在assets中
+pub fn user_map_pack_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("assets"))
+        .join(GAME_USER_MAP_PACK_PATH)
+}
+
+///编辑器保存的用户地图包
+pub fn user_map_pack_path() -> PathBuf {
+    user_map_pack_dir().join(GAME_USER_MAP_PACK_FILE)
+}
+
+///通关进度中地图包的id,用户地图包加上前缀避免和随游戏发布的同名
+fn map_pack_id(path: &Path, source: MapPackSource) -> String {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    match source {
+        MapPackSource::BuiltIn => String::new(),
+        MapPackSource::Shipped => stem.to_string(),
+        MapPackSource::User => format!("user/{}", stem),
+    }
+}
+
+///新建用户地图包,保存前只在内存中
+pub fn new_user_map_pack(maps: Vec<GameMap>) -> MapPack {
+    let path = user_map_pack_path();
+    MapPack {
+        id: map_pack_id(&path, MapPackSource::User),
+        path,
+        source: MapPackSource::User,
+        maps: GameMapCollection {
+            maps,
+            ..Default::default()
+        },
+    }
+}
+
+///读取随游戏发布的地图包和用户地图包,按文件名排序,格式错误或没有地图的文件跳过
+pub fn load_map_packs() -> Vec<MapPack> {
+    let mut packs = Vec::new();
+    for (dir, source) in [
+        (PathBuf::from(GAME_MAP_PACK_PATH), MapPackSource::Shipped),
+        (user_map_pack_dir(), MapPackSource::User),
+    ] {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "ron"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            match load_map_pack(&path) {
+                Ok(maps) => packs.push(MapPack {
+                    id: map_pack_id(&path, source),
+                    path,
+                    source,
+                    maps,
+                }),
+                Err(err) => println!("load map pack {} error: {}", path.display(), err),
+            }
+        }
+    }
+    packs
+}
+
+///读取通关进度,文件不存在或格式错误时从头开始
+pub fn load_progress() -> GameProgress {
+    std::fs::read_to_string(GAME_PROGRESS_PATH)
+        .ok()
+        .and_then(|progress| from_str(&progress).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_progress(progress: &GameProgress) {
+    if let Ok(progress_str) = to_string(progress) {
+        if let Err(err) = std::fs::write(GAME_PROGRESS_PATH, progress_str) {
+            println!("save progress error: {}", err);
+        }
+    }
+}
+
+///读取游戏设置,文件不存在或格式错误时使用默认设置
+pub fn load_settings() -> GameSettings {
+    std::fs::read_to_string(GAME_SETTINGS_PATH)
+        .ok()
+        .and_then(|settings| from_str(&settings).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_settings(settings: &GameSettings) {
+    if let Ok(settings_str) = to_string_pretty(settings, PrettyConfig::default()) {
+        if let Err(err) = std::fs::write(GAME_SETTINGS_PATH, settings_str) {
+            println!("save settings error: {}", err);
+        }
+    }
+}
+
+///读取编辑器的图章库,文件不存在或格式错误时为空
+pub fn load_stamps() -> EditorStampLibrary {
+    std::fs::read_to_string(GAME_STAMPS_PATH)
+        .ok()
+        .and_then(|stamps| from_str(&stamps).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_stamps(library: &EditorStampLibrary) -> Result<(), MapSaveError> {
+    let stamps_str = to_string_pretty(library, PrettyConfig::new().depth_limit(3))?;
+    fs::write(GAME_STAMPS_PATH, stamps_str)?;
+    Ok(())
+}
 
-pub fn save_map(map: &GameMapCollection) {
-    let map_str = to_string(map).unwrap();
-    std::fs::write("assets/map.ron", map_str).unwrap();
+///按键的显示名称
+pub fn key_name(key: KeyCode) -> String {
+    let name = format!("{:?}", key);
+    name.strip_prefix("Key")
+        .or_else(|| name.strip_prefix("Digit"))
+        .unwrap_or(&name)
+        .to_string()
 }
 
 pub fn position_to_pos(position: (f32, f32)) -> (usize, usize) {
@@ -88,7 +276,7 @@ fn heuristic(a: (usize, usize), b: (usize, usize)) -> usize {
 
 fn get_neighbors(
     (x, y, w, h): (usize, usize, usize, usize),
-    grid: &Vec<Vec<usize>>,
+    grid: &[Vec<usize>],
 ) -> Vec<(usize, usize)> {
     let mut neighbors = Vec::new();
     let directions = [(0, -1), (-1, 0), (1, 0), (0, 1)];
@@ -129,7 +317,7 @@ fn get_neighbors(
 }
 
 pub fn a_star(
-    grid: &Vec<Vec<usize>>,
+    grid: &[Vec<usize>],
     start: (usize, usize),
     goal: (usize, usize),
 ) -> Option<Vec<(usize, usize)>> {
@@ -170,6 +358,118 @@ pub fn a_star(
     None
 }
 
+///从start出发的坦克(2x2)能到达的位置,值为到达该位置的上一个位置,用于还原最短路线
+pub fn flood_fill(grid: &[Vec<usize>], start: (usize, usize)) -> Vec<Vec<Option<(usize, usize)>>> {
+    let mut parent = vec![vec![None; grid[0].len()]; grid.len()];
+    let mut queue = VecDeque::from([start]);
+    parent[start.0][start.1] = Some(start);
+    while let Some(pos) = queue.pop_front() {
+        for next in get_neighbors((pos.0, pos.1, 2, 2), grid) {
+            if parent[next.0][next.1].is_none() {
+                parent[next.0][next.1] = Some(pos);
+                queue.push_back(next);
+            }
+        }
+    }
+    parent
+}
+
+fn fill_path(
+    parent: &[Vec<Option<(usize, usize)>>],
+    start: (usize, usize),
+    goal: (usize, usize),
+) -> Option<Vec<(usize, usize)>> {
+    let mut path = vec![goal];
+    let mut current = goal;
+    while current != start {
+        current = parent[current.0][current.1]?;
+        path.push(current);
+    }
+    path.reverse();
+    Some(path)
+}
+
+///出生点的块类型和到老家的最短路线,到不了时为None
+pub struct SpawnPath {
+    pub block: usize,
+    pub path: Option<Vec<(usize, usize)>>,
+}
+///编辑器中对地图的分析结果
+pub struct MapAnalysis {
+    ///从任一出生点出发的坦克能经过的格子
+    pub reachable: Vec<Vec<bool>>,
+    pub spawn_paths: Vec<SpawnPath>,
+    ///打掉所有砖块后敌人仍然到不了老家,即老家被铁块或水完全围住
+    pub home_enclosed: bool,
+}
+
+impl MapAnalysis {
+    pub fn home_reachable(&self) -> bool {
+        self.spawn_paths
+            .iter()
+            .any(|spawn| spawn.block >= 9 && spawn.path.is_some())
+    }
+}
+
+///从每个出生点泛洪,得到坦克能到达的格子和到老家的最短路线
+pub fn analyze_map(game_map: &GameMap) -> MapAnalysis {
+    //游戏中玩家出生点是空地
+    let grid: Vec<Vec<usize>> = game_map
+        .map
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|block| if [7, 8].contains(block) { 0 } else { *block })
+                .collect()
+        })
+        .collect();
+    let open_grid: Vec<Vec<usize>> = grid
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|block| if *block == 1 { 0 } else { *block })
+                .collect()
+        })
+        .collect();
+    let blocks = game_map.to_blocks();
+    let home = blocks
+        .iter()
+        .find(|block| block.block == 6)
+        .map(|block| (block.row, block.col));
+    let mut reachable = vec![vec![false; grid[0].len()]; grid.len()];
+    let mut spawn_paths = vec![];
+    let mut home_enclosed = true;
+    for block in blocks
+        .iter()
+        .filter(|block| (7..=11).contains(&block.block))
+    {
+        let start = (block.row, block.col);
+        let parent = flood_fill(&grid, start);
+        for (r, row) in parent.iter().enumerate() {
+            for (c, pos) in row.iter().enumerate() {
+                if pos.is_some() {
+                    for (dr, dc) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+                        reachable[r + dr][c + dc] = true;
+                    }
+                }
+            }
+        }
+        let path = home.and_then(|home| fill_path(&parent, start, home));
+        if let Some(home) = home.filter(|_| block.block >= 9) {
+            home_enclosed &= flood_fill(&open_grid, start)[home.0][home.1].is_none();
+        }
+        spawn_paths.push(SpawnPath {
+            block: block.block,
+            path,
+        });
+    }
+    MapAnalysis {
+        reachable,
+        spawn_paths,
+        home_enclosed: home.is_some() && home_enclosed,
+    }
+}
+
 pub fn random_direction_neighbour(
     (x, y, w, h): (usize, usize, usize, usize),
     grid: &Vec<Vec<usize>>,
@@ -2,6 +2,7 @@ use super::{
     animate::Animator,
     class::{ChildCommand, Class},
 };
+use crate::res::Tr;
 use bevy::prelude::*;
 
 macro_rules! widget_bundle {
@@ -123,6 +124,17 @@ pub fn node_text_a<P>(
         .id()
 }
 
+///text为翻译键,显示时替换为当前语言的文字
+pub fn node_text_t<P>(
+    key: impl Into<String>,
+    class: impl Class<P>,
+    commands: &mut ChildBuilder,
+    relate: impl Bundle,
+) -> Entity {
+    let key = key.into();
+    node_text(key.clone(), class, commands, (Tr::new(key), relate))
+}
+
 pub fn text2d<P>(
     text: impl Into<String>,
     class: impl Class<P>,
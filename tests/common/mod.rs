@@ -0,0 +1,31 @@
+use bevy_tank::{
+    res::{GameMap, GameMapCollection},
+    utils::ron::RonAsset,
+};
+
+///assets/map.ron中的内置地图
+pub fn assets_maps() -> Vec<GameMap> {
+    let bytes = std::fs::read("assets/map.ron").unwrap();
+    GameMapCollection::from_ron(&bytes).unwrap().maps
+}
+
+///地图信息都不是默认值的地图
+pub fn metadata_map() -> GameMap {
+    let mut game_map = GameMap::new_blank("地图");
+    game_map.author = "someone".to_string();
+    game_map.music = "start".to_string();
+    game_map.enemy_roster = [3, 200, 0];
+    game_map.time_limit = 600;
+    game_map.power_up_drop_rate = 0;
+    game_map
+}
+
+///按序列化结果比较两张地图
+pub fn assert_same_map(actual: &GameMap, expected: &GameMap) {
+    assert_eq!(
+        serde_ron::to_string(actual).unwrap(),
+        serde_ron::to_string(expected).unwrap(),
+        "{}",
+        expected.name
+    );
+}
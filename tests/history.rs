@@ -0,0 +1,73 @@
+use bevy_tank::res::{EditorAction, EditorHistory, GameMap, GameMapCollection};
+
+fn collection(names: &[&str]) -> GameMapCollection {
+    GameMapCollection {
+        maps: names.iter().map(|name| GameMap::new_blank(*name)).collect(),
+        ..Default::default()
+    }
+}
+
+fn names(gm_map: &GameMapCollection) -> Vec<&str> {
+    gm_map
+        .maps
+        .iter()
+        .map(|game_map| game_map.name.as_str())
+        .collect()
+}
+
+#[test]
+fn undo_redo_add_and_remove_map() {
+    let mut gm_map = collection(&["a", "b"]);
+    let mut history = EditorHistory::default();
+    gm_map.maps.push(GameMap::new_blank("c"));
+    history.push_add(2, &gm_map.maps[2]);
+    let removed = gm_map.maps.remove(0);
+    history.push_remove(0, removed);
+    assert_eq!(names(&gm_map), ["b", "c"]);
+
+    assert_eq!(history.undo(0, &mut gm_map), Some(0));
+    assert_eq!(names(&gm_map), ["a", "b", "c"]);
+    assert_eq!(history.undo(0, &mut gm_map), Some(1));
+    assert_eq!(names(&gm_map), ["a", "b"]);
+    assert_eq!(history.undo(0, &mut gm_map), None);
+
+    assert_eq!(history.redo(0, &mut gm_map), Some(2));
+    assert_eq!(history.redo(0, &mut gm_map), Some(0));
+    assert_eq!(names(&gm_map), ["b", "c"]);
+}
+
+#[test]
+fn removed_map_keeps_its_history() {
+    let mut gm_map = collection(&["a", "b"]);
+    let mut history = EditorHistory::default();
+    let before = gm_map.maps[1].map.clone();
+    gm_map.maps[1].map[0][0] = 1;
+    let action = EditorAction::diff(&before, &gm_map.maps[1].map).unwrap();
+    history.push(1, action);
+    let removed = gm_map.maps.remove(1);
+    history.push_remove(1, removed);
+
+    //先撤销删除,再撤销删除之前在这张地图上的修改
+    assert_eq!(history.undo(0, &mut gm_map), Some(1));
+    assert_eq!(gm_map.maps[1].map[0][0], 1);
+    assert_eq!(history.undo(1, &mut gm_map), Some(1));
+    assert_eq!(gm_map.maps[1].map, before);
+    //新的操作清空重做
+    history.push(
+        1,
+        EditorAction::Rename {
+            before: "b".into(),
+            after: "x".into(),
+        },
+    );
+    assert_eq!(history.redo(1, &mut gm_map), None);
+}
+
+#[test]
+fn keeps_at_least_one_map() {
+    let mut gm_map = collection(&["a"]);
+    let mut history = EditorHistory::default();
+    history.push_add(0, &gm_map.maps[0]);
+    assert_eq!(history.undo(0, &mut gm_map), None);
+    assert_eq!(names(&gm_map), ["a"]);
+}
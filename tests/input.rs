@@ -0,0 +1,146 @@
+use bevy::{
+    ecs::system::RunSystemOnce,
+    input::gamepad::{
+        gamepad_connection_system, GamepadConnection, GamepadConnectionEvent, GamepadInfo,
+    },
+    prelude::*,
+};
+use bevy_tank::{
+    res::{GameDirection, InputDevice, MenuInput, GAME_GAMEPAD_DEADZONE, PLAYER1_KEYS},
+    utils::input::{apply_directions, stick_direction, update_menu_input, GameInput},
+};
+
+const PAD: Gamepad = Gamepad { id: 0 };
+
+///不加载InputPlugin,手柄的输入直接写入资源,只用它的连接系统注册手柄
+fn input_app() -> App {
+    let mut app = App::new();
+    app.init_resource::<ButtonInput<KeyCode>>()
+        .init_resource::<Gamepads>()
+        .init_resource::<ButtonInput<GamepadButton>>()
+        .init_resource::<Axis<GamepadAxis>>()
+        .init_resource::<Axis<GamepadButton>>()
+        .init_resource::<MenuInput>()
+        .add_event::<GamepadConnectionEvent>()
+        .add_systems(PreUpdate, gamepad_connection_system)
+        .add_systems(Update, update_menu_input);
+    app.world.send_event(GamepadConnectionEvent::new(
+        PAD,
+        GamepadConnection::Connected(GamepadInfo {
+            name: "test pad".to_string(),
+        }),
+    ));
+    app.update();
+    app
+}
+
+fn set_stick(app: &mut App, x: f32, y: f32) {
+    let mut axes = app.world.resource_mut::<Axis<GamepadAxis>>();
+    axes.set(GamepadAxis::new(PAD, GamepadAxisType::LeftStickX), x);
+    axes.set(GamepadAxis::new(PAD, GamepadAxisType::LeftStickY), y);
+}
+
+fn press(app: &mut App, button: GamepadButtonType) {
+    let mut buttons = app.world.resource_mut::<ButtonInput<GamepadButton>>();
+    buttons.clear();
+    buttons.press(GamepadButton::new(PAD, button));
+}
+
+fn gamepad_directions(app: &mut App) -> [bool; 4] {
+    app.world.run_system_once(|input: GameInput| {
+        input.directions(InputDevice::Gamepad(PAD.id), &PLAYER1_KEYS)
+    })
+}
+
+#[test]
+fn stick_moves_menu_once() {
+    let mut app = input_app();
+    set_stick(&mut app, 0.8, 0.1);
+    app.update();
+    let menu_input = app.world.resource::<MenuInput>();
+    assert!(menu_input.right && !menu_input.up);
+    //继续推着摇杆不会重复触发
+    app.update();
+    assert!(!app.world.resource::<MenuInput>().right);
+    set_stick(&mut app, 0., 0.);
+    app.update();
+    set_stick(&mut app, 0., -1.);
+    app.update();
+    assert!(app.world.resource::<MenuInput>().down);
+}
+
+#[test]
+fn gamepad_buttons_drive_menu() {
+    let mut app = input_app();
+    press(&mut app, GamepadButtonType::DPadUp);
+    app.update();
+    assert!(app.world.resource::<MenuInput>().up);
+    press(&mut app, GamepadButtonType::South);
+    app.update();
+    assert!(app.world.resource::<MenuInput>().confirm);
+    press(&mut app, GamepadButtonType::East);
+    app.update();
+    let menu_input = app.world.resource::<MenuInput>();
+    assert!(menu_input.back && !menu_input.confirm);
+}
+
+#[test]
+fn gamepad_directions_merge_dpad_and_stick() {
+    let mut app = input_app();
+    set_stick(&mut app, -0.9, 0.);
+    press(&mut app, GamepadButtonType::DPadUp);
+    assert_eq!(gamepad_directions(&mut app), [true, false, true, false]);
+    set_stick(&mut app, 0.1, 0.1);
+    assert_eq!(gamepad_directions(&mut app), [true, false, false, false]);
+    press(&mut app, GamepadButtonType::West);
+    let fire = app.world.run_system_once(|input: GameInput| {
+        input.fire(InputDevice::Gamepad(PAD.id), &PLAYER1_KEYS)
+    });
+    assert!(fire);
+}
+
+#[test]
+fn stick_direction_deadzone() {
+    let deadzone = GAME_GAMEPAD_DEADZONE;
+    assert_eq!(stick_direction(Vec2::ZERO, deadzone), None);
+    assert_eq!(
+        stick_direction(Vec2::new(deadzone - 0.01, 0.), deadzone),
+        None
+    );
+    assert_eq!(
+        stick_direction(Vec2::new(deadzone, 0.), deadzone),
+        Some(GameDirection::Right)
+    );
+    assert_eq!(
+        stick_direction(Vec2::new(0., -deadzone), deadzone),
+        Some(GameDirection::Down)
+    );
+    //斜向时取偏移较大的轴,相等时取上下
+    assert_eq!(
+        stick_direction(Vec2::new(-0.5, 0.4), deadzone),
+        Some(GameDirection::Left)
+    );
+    assert_eq!(
+        stick_direction(Vec2::new(0.5, 0.5), deadzone),
+        Some(GameDirection::Up)
+    );
+}
+
+#[test]
+fn apply_directions_keeps_press_order() {
+    let mut stack = Vec::new();
+    let stick = |x: f32, y: f32| {
+        let direction = stick_direction(Vec2::new(x, y), GAME_GAMEPAD_DEADZONE);
+        GameDirection::ALL.map(|d| Some(d) == direction)
+    };
+    apply_directions(&mut stack, stick(0., 0.29));
+    assert!(stack.is_empty());
+    apply_directions(&mut stack, stick(0., 0.3));
+    assert_eq!(stack, [GameDirection::Up]);
+    apply_directions(&mut stack, [true, false, false, true]);
+    assert_eq!(stack, [GameDirection::Up, GameDirection::Right]);
+    apply_directions(&mut stack, [false, false, false, true]);
+    assert_eq!(stack, [GameDirection::Right]);
+    apply_directions(&mut stack, stick(0.1, 0.1));
+    assert!(stack.is_empty());
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use bevy_tank::utils::map_io::{
+    from_ascii, from_tiled_json, read_maps, to_ascii, to_tiled_json, MapFormat,
+};
+use common::{assert_same_map, assets_maps, metadata_map};
+
+#[test]
+fn round_trip_ascii() {
+    let maps = assets_maps();
+    assert!(!maps.is_empty());
+    for game_map in maps.iter() {
+        let decoded = from_ascii(&to_ascii(game_map)).unwrap();
+        assert_eq!(decoded.len(), 1);
+        assert_same_map(&decoded[0], game_map);
+    }
+    //多张地图之间空一行
+    let text = maps.iter().map(to_ascii).collect::<Vec<_>>().join("\n");
+    let decoded = read_maps(&text, MapFormat::Ascii).unwrap();
+    assert_eq!(decoded.len(), maps.len());
+}
+
+#[test]
+fn round_trip_tiled_json() {
+    for game_map in assets_maps() {
+        let decoded = from_tiled_json(&to_tiled_json(&game_map).unwrap()).unwrap();
+        assert_same_map(&decoded, &game_map);
+    }
+}
+
+#[test]
+fn round_trip_metadata() {
+    let game_map = metadata_map();
+    assert_same_map(&from_ascii(&to_ascii(&game_map)).unwrap()[0], &game_map);
+    let json = to_tiled_json(&game_map).unwrap();
+    assert_same_map(&from_tiled_json(&json).unwrap(), &game_map);
+}
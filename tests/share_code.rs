@@ -0,0 +1,46 @@
+mod common;
+
+use bevy_tank::utils::share_code::{decode_map, encode_map, ShareCodeError};
+use common::{assert_same_map, assets_maps, metadata_map};
+
+#[test]
+fn round_trip_assets_maps() {
+    let maps = assets_maps();
+    assert!(!maps.is_empty());
+    for game_map in maps {
+        let decoded = decode_map(&encode_map(&game_map)).unwrap();
+        assert_same_map(&decoded, &game_map);
+    }
+}
+
+#[test]
+fn round_trip_metadata() {
+    let game_map = metadata_map();
+    assert_same_map(&decode_map(&encode_map(&game_map)).unwrap(), &game_map);
+}
+
+#[test]
+fn code_ignores_whitespace() {
+    let game_map = &assets_maps()[0];
+    let code = encode_map(game_map);
+    let (head, tail) = code.split_at(code.len() / 2);
+    assert!(decode_map(&format!(" {}\n{} ", head, tail)).is_ok());
+}
+
+#[test]
+fn rejects_broken_codes() {
+    let code = encode_map(&assets_maps()[0]);
+    assert!(matches!(
+        decode_map(&code[..code.len() / 8 * 4]),
+        Err(ShareCodeError::Truncated)
+    ));
+    assert!(matches!(
+        decode_map("not a code!"),
+        Err(ShareCodeError::Base64(_))
+    ));
+    assert!(matches!(decode_map("Ag"), Err(ShareCodeError::Version(2))));
+    assert!(matches!(
+        decode_map("AQAAAAAAAAAAD_____________________________________________________8"),
+        Err(ShareCodeError::Overflow)
+    ));
+}